@@ -0,0 +1,816 @@
+// Cache de thumbnails em disco
+//
+// Dois formatos:
+// - Pasta: um cache_m3u/<id>.jpg por vídeo (formato original, simples de inspecionar)
+// - Pacote: cache_m3u/thumbs.pack (arquivo só de acréscimo com os bytes do JPEG e o RGBA já
+//   decodificado e reduzido) + cache_m3u/thumbs.idx (índice JSON com posições, datas e falhas).
+//   Na inicialização só o índice é lido, e carregar uma thumbnail é um seek + read, sem decodificar JPEG
+//   (com 1000 thumbnails, abrir e carregar tudo leva ~80 ms contra ~1,9 s da pasta; ver o teste
+//   tempo_de_abertura_pasta_e_pacote).
+//   A compactação grava um pacote novo (thumbs.<n>.pack) e só depois o índice que aponta para ele,
+//   então um crash no meio nunca deixa o índice apontando para o pacote errado.
+use crate::config::CacheBackend;
+use crate::util::{mtime_secs, now_secs, write_atomic};
+use egui::ColorImage;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::Sender;
+use std::sync::{Arc, Mutex};
+
+pub const CACHE_DIR: &str = "cache_m3u";
+const PACK_FILE: &str = "thumbs.pack";
+const INDEX_FILE: &str = "thumbs.idx";
+const DIR_META_FILE: &str = "thumbs_meta.json"; // Datas, validadores e falhas do formato Pasta
+const INDEX_VERSION: u32 = 1;
+
+// Largura máxima do RGBA pré-decodificado guardado no pacote
+const RGBA_MAX_WIDTH: u32 = 320;
+const RGBA_MAX_HEIGHT: u32 = 180;
+
+// Depois de uma falha (ex.: 404), só tentar baixar de novo após este intervalo
+const FAILURE_RETRY_SECS: u64 = 24 * 60 * 60;
+
+// Compactar o pacote na abertura quando mais da metade dele for lixo
+const COMPACT_MIN_GARBAGE: u64 = 1024 * 1024;
+
+pub type SharedCache = Arc<Mutex<ThumbCache>>;
+
+// Thumbnail baixada, já decodificada e reduzida. É preparada antes de travar o cache, que a
+// interface trava a cada quadro.
+pub struct Thumbnail {
+    bytes: Vec<u8>, // Arquivo como veio do servidor
+    rgba: Vec<u8>,
+    width: u32,
+    height: u32,
+}
+
+impl Thumbnail {
+    pub fn decode(bytes: Vec<u8>) -> io::Result<Self> {
+        let image = image::load_from_memory(&bytes).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+        let image = if image.width() > RGBA_MAX_WIDTH || image.height() > RGBA_MAX_HEIGHT {
+            image.resize(RGBA_MAX_WIDTH, RGBA_MAX_HEIGHT, image::imageops::FilterType::Triangle)
+        } else {
+            image
+        };
+        let rgba = image.to_rgba8();
+        let (width, height) = rgba.dimensions();
+        Ok(Self { bytes, rgba: rgba.into_raw(), width, height })
+    }
+
+    fn color_image(&self) -> ColorImage {
+        ColorImage::from_rgba_unmultiplied([self.width as usize, self.height as usize], &self.rgba)
+    }
+}
+
+// Informações sobre o download de uma thumbnail
+#[derive(Serialize, Deserialize, Clone, Default, Debug)]
+#[serde(default)]
+pub struct ThumbMeta {
+    pub fetched_at: u64,              // Quando os bytes foram baixados (segundos Unix)
+    pub etag: Option<String>,         // Cabeçalho ETag da resposta
+    pub last_modified: Option<String>, // Cabeçalho Last-Modified da resposta
+    pub max_age: Option<u64>,         // max-age do Cache-Control (0 para no-cache/no-store)
+    pub failures: u32,                // Falhas consecutivas ao baixar
+    pub last_failure: u64,            // Quando ocorreu a última falha (segundos Unix)
+}
+
+impl ThumbMeta {
+    fn is_failed(&self) -> bool {
+        self.failures > 0 && now_secs().saturating_sub(self.last_failure) < FAILURE_RETRY_SECS
+    }
+
+    // A thumbnail deve ser revalidada no servidor? O Cache-Control do servidor manda (no-cache e
+    // max-age curto revalidam a cada abertura, com requisição condicional); min_interval só vale
+    // quando o servidor não disse nada.
+    fn is_stale(&self, min_interval: u64) -> bool {
+        self.is_stale_at(min_interval, now_secs())
+    }
+
+    fn is_stale_at(&self, min_interval: u64, now: u64) -> bool {
+        let max_age = self.max_age.unwrap_or(min_interval);
+        now >= self.fetched_at.saturating_add(max_age)
+    }
+}
+
+// Operações que cada formato de cache precisa oferecer
+trait ThumbStore: Send {
+    fn contains(&mut self, id: &str) -> bool;
+    fn image_source(&mut self, id: &str) -> Option<ImageSource>;
+    fn insert(&mut self, id: &str, thumbnail: &Thumbnail, meta: ThumbMeta) -> io::Result<()>;
+    fn import_loose(&mut self, id: &str, thumbnail: &Thumbnail, meta: ThumbMeta);
+    fn meta(&mut self, id: &str) -> Option<ThumbMeta>; // Só para thumbnails presentes no cache
+    fn set_meta(&mut self, id: &str, meta: ThumbMeta);
+    fn mark_failed(&mut self, id: &str);
+    fn is_failed(&self, id: &str) -> bool;
+    fn flush(&mut self) -> io::Result<()>;
+    fn summary(&self) -> String;
+}
+
+pub struct ThumbCache {
+    store: Box<dyn ThumbStore>,
+    generation: u64, // Incrementado a cada thumbnail nova, para a GUI saber quando procurar texturas
+    updated: Vec<String>, // IDs cujas thumbnails mudaram e precisam ter a textura recarregada
+}
+
+impl ThumbCache {
+    pub fn open(backend: CacheBackend) -> Self {
+        // Criar diretório de cache se não existir
+        fs::create_dir_all(CACHE_DIR).unwrap_or_else(|_| {
+            println!("Não foi possível criar o diretório de cache");
+        });
+
+        let store: Box<dyn ThumbStore> = match backend {
+            CacheBackend::Pasta => Box::new(DirStore::open(Path::new(CACHE_DIR))),
+            CacheBackend::Pacote => match PackStore::open(Path::new(CACHE_DIR)) {
+                Ok(store) => Box::new(store),
+                Err(e) => {
+                    println!("Não foi possível abrir o pacote de thumbnails ({}), usando a pasta", e);
+                    Box::new(DirStore::open(Path::new(CACHE_DIR)))
+                }
+            },
+        };
+
+        Self {
+            store,
+            generation: 1,
+            updated: Vec::new(),
+        }
+    }
+
+    // Cache em formato Pacote numa pasta qualquer
+    #[cfg(test)]
+    pub fn in_dir(dir: &Path) -> io::Result<Self> {
+        Ok(Self {
+            store: Box::new(PackStore::open(dir)?),
+            generation: 1,
+            updated: Vec::new(),
+        })
+    }
+
+    pub fn shared(backend: CacheBackend) -> SharedCache {
+        Arc::new(Mutex::new(Self::open(backend)))
+    }
+
+    pub fn generation(&self) -> u64 {
+        self.generation
+    }
+
+    // Verifica se a thumbnail precisa ser baixada
+    pub fn needs_download(&mut self, id: &str) -> bool {
+        !self.store.contains(id) && !self.store.is_failed(id)
+    }
+
+    // Verifica se a thumbnail já baixada deve ser conferida de novo no servidor
+    pub fn needs_revalidation(&mut self, id: &str, min_interval: u64) -> bool {
+        !self.store.is_failed(id) && self.store.meta(id).is_some_and(|m| m.is_stale(min_interval))
+    }
+
+    // Validadores (ETag/Last-Modified) da thumbnail guardada, para uma requisição condicional
+    pub fn meta(&mut self, id: &str) -> Option<ThumbMeta> {
+        self.store.meta(id)
+    }
+
+    #[cfg(test)]
+    pub fn load_image(&mut self, id: &str) -> Option<ColorImage> {
+        match self.store.image_source(id)?.read()? {
+            LoadedImage::Ready(image) => Some(image),
+            LoadedImage::Imported(thumbnail, meta) => {
+                self.store.import_loose(id, &thumbnail, meta);
+                Some(thumbnail.color_image())
+            }
+        }
+    }
+
+    pub fn insert(&mut self, id: &str, thumbnail: &Thumbnail, meta: ThumbMeta) {
+        let replaced = self.store.meta(id).is_some();
+        match self.store.insert(id, thumbnail, meta) {
+            Ok(()) => {
+                self.generation += 1;
+                if replaced {
+                    self.updated.push(id.to_string());
+                }
+            }
+            Err(_) => self.store.mark_failed(id),
+        }
+    }
+
+    // O servidor respondeu 304: a thumbnail continua válida, só atualizar datas e validadores
+    pub fn touch(&mut self, id: &str, meta: ThumbMeta) {
+        self.store.set_meta(id, meta);
+    }
+
+    // IDs de thumbnails substituídas desde a última chamada
+    pub fn take_updated(&mut self) -> Vec<String> {
+        std::mem::take(&mut self.updated)
+    }
+
+    pub fn mark_failed(&mut self, id: &str) {
+        self.store.mark_failed(id);
+    }
+
+    pub fn flush(&mut self) {
+        if let Err(e) = self.store.flush() {
+            println!("Não foi possível salvar o índice do cache: {}", e);
+        }
+    }
+
+    pub fn summary(&self) -> String {
+        self.store.summary()
+    }
+}
+
+// Carrega as imagens das thumbnails indicadas; roda fora da thread da interface. O cache só fica
+// travado para achar cada imagem: ler o arquivo e decodificar acontecem sem o lock, que a interface
+// trava a cada quadro. Para quando a interface descarta o receptor.
+pub fn load_images(cache: &SharedCache, ids: Vec<String>, tx: Sender<(String, ColorImage)>, ctx: egui::Context) {
+    for id in ids {
+        let source = cache.lock().unwrap().store.image_source(&id);
+        let image = match source.and_then(ImageSource::read) {
+            Some(LoadedImage::Ready(image)) => image,
+            Some(LoadedImage::Imported(thumbnail, meta)) => {
+                cache.lock().unwrap().store.import_loose(&id, &thumbnail, meta);
+                thumbnail.color_image()
+            }
+            None => continue,
+        };
+        if tx.send((id, image)).is_err() {
+            return;
+        }
+        ctx.request_repaint();
+    }
+    // Avisar a interface de que a leitura acabou, para ela procurar thumbnails que chegaram nesse meio tempo
+    drop(tx);
+    ctx.request_repaint();
+}
+
+// Onde está a imagem de uma thumbnail
+enum ImageSource {
+    Loose(PathBuf),  // <id>.jpg do formato Pasta
+    Import(PathBuf), // <id>.jpg antigo que o Pacote ainda não importou
+    Pack { path: PathBuf, blob: Blob, width: u32, height: u32 },
+}
+
+// Imagem lida; um .jpg importado volta também decodificado e reduzido, para entrar no pacote
+enum LoadedImage {
+    Ready(ColorImage),
+    Imported(Thumbnail, ThumbMeta),
+}
+
+impl ImageSource {
+    fn read(self) -> Option<LoadedImage> {
+        match self {
+            ImageSource::Loose(path) => image::open(path).ok().map(|image| LoadedImage::Ready(to_color_image(image))),
+            ImageSource::Import(path) => {
+                let thumbnail = Thumbnail::decode(fs::read(&path).ok()?).ok()?;
+                Some(LoadedImage::Imported(thumbnail, loose_meta(&path)))
+            }
+            ImageSource::Pack { path, blob, width, height } => {
+                // Um índice corrompido pode trazer dimensões que estouram a multiplicação
+                let expected = (width as usize).checked_mul(height as usize).and_then(|n| n.checked_mul(4))?;
+                if blob.len != expected as u64 {
+                    return None;
+                }
+                let mut pixels = vec![0; expected];
+                let mut pack = File::open(path).ok()?;
+                pack.seek(SeekFrom::Start(blob.offset)).ok()?;
+                pack.read_exact(&mut pixels).ok()?;
+                Some(LoadedImage::Ready(ColorImage::from_rgba_unmultiplied(
+                    [width as usize, height as usize],
+                    &pixels,
+                )))
+            }
+        }
+    }
+}
+
+// Converte uma imagem decodificada para o formato de textura do egui
+fn to_color_image(image: image::DynamicImage) -> ColorImage {
+    let image = image.to_rgba8();
+    let size = [image.width() as _, image.height() as _];
+    ColorImage::from_rgba_unmultiplied(size, &image.into_raw())
+}
+
+fn loose_path(dir: &Path, id: &str) -> PathBuf {
+    dir.join(format!("{}.jpg", id))
+}
+
+// Formato original: um arquivo por vídeo, com as informações de download em thumbs_meta.json
+struct DirStore {
+    dir: PathBuf,
+    meta: HashMap<String, ThumbMeta>,
+    dirty: bool,
+    count: usize, // .jpg na pasta, contados na abertura (o resumo é mostrado a cada quadro)
+}
+
+impl DirStore {
+    fn open(dir: &Path) -> Self {
+        let meta = fs::read(dir.join(DIR_META_FILE))
+            .ok()
+            .and_then(|bytes| serde_json::from_slice(&bytes).ok())
+            .unwrap_or_default();
+        let count = fs::read_dir(dir)
+            .map(|dir| dir.filter(|e| e.as_ref().is_ok_and(|e| e.path().extension().is_some_and(|x| x == "jpg"))).count())
+            .unwrap_or(0);
+        Self { dir: dir.to_path_buf(), meta, dirty: false, count }
+    }
+}
+
+impl ThumbStore for DirStore {
+    fn contains(&mut self, id: &str) -> bool {
+        loose_path(&self.dir, id).exists()
+    }
+
+    fn image_source(&mut self, id: &str) -> Option<ImageSource> {
+        let path = loose_path(&self.dir, id);
+        path.exists().then_some(ImageSource::Loose(path))
+    }
+
+    // Os .jpg já são o formato da pasta
+    fn import_loose(&mut self, _id: &str, _thumbnail: &Thumbnail, _meta: ThumbMeta) {}
+
+    fn insert(&mut self, id: &str, thumbnail: &Thumbnail, meta: ThumbMeta) -> io::Result<()> {
+        let path = loose_path(&self.dir, id);
+        let existed = path.exists();
+        File::create(&path)?.write_all(&thumbnail.bytes)?;
+        if !existed {
+            self.count += 1;
+        }
+        self.meta.insert(id.to_string(), meta);
+        self.dirty = true;
+        Ok(())
+    }
+
+    fn meta(&mut self, id: &str) -> Option<ThumbMeta> {
+        let path = loose_path(&self.dir, id);
+        if !path.exists() {
+            return None;
+        }
+        // .jpg baixados antes de existir thumbs_meta.json usam a data do arquivo
+        Some(self.meta.get(id).cloned().unwrap_or_else(|| loose_meta(&path)))
+    }
+
+    fn set_meta(&mut self, id: &str, meta: ThumbMeta) {
+        self.meta.insert(id.to_string(), meta);
+        self.dirty = true;
+    }
+
+    fn mark_failed(&mut self, id: &str) {
+        let meta = self.meta.entry(id.to_string()).or_default();
+        meta.failures += 1;
+        meta.last_failure = now_secs();
+        self.dirty = true;
+    }
+
+    fn is_failed(&self, id: &str) -> bool {
+        self.meta.get(id).is_some_and(ThumbMeta::is_failed)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        if !self.dirty {
+            return Ok(());
+        }
+        let json = serde_json::to_vec(&self.meta)?;
+        write_atomic(&self.dir.join(DIR_META_FILE), &json)?;
+        self.dirty = false;
+        Ok(())
+    }
+
+    fn summary(&self) -> String {
+        format!("{} thumbnails em {}", self.count, self.dir.display())
+    }
+}
+
+// Trecho de bytes dentro do pacote
+#[derive(Serialize, Deserialize, Clone, Copy, Debug)]
+struct Blob {
+    offset: u64,
+    len: u64,
+}
+
+impl Blob {
+    fn end(&self) -> u64 {
+        self.offset + self.len
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Default, Debug)]
+#[serde(default)]
+struct PackEntry {
+    jpeg: Option<Blob>,         // Bytes originais baixados
+    rgba: Option<Blob>,         // Pixels RGBA já reduzidos
+    width: u32,                 // Dimensões do RGBA
+    height: u32,
+    meta: ThumbMeta,
+}
+
+#[derive(Serialize, Deserialize, Default)]
+#[serde(default)]
+struct PackIndex {
+    version: u32,
+    pack: u64,    // Número do pacote a que as posições se referem (0: thumbs.pack)
+    garbage: u64, // Bytes do pacote que não são mais referenciados
+    entries: HashMap<String, PackEntry>,
+}
+
+// Nome do arquivo do pacote número `n`
+fn pack_name(n: u64) -> String {
+    if n == 0 {
+        PACK_FILE.to_string()
+    } else {
+        format!("thumbs.{}.pack", n)
+    }
+}
+
+struct PackStore {
+    dir: PathBuf,
+    pack: File,
+    pack_len: u64,
+    index: PackIndex,
+    dirty: bool,
+    missing_loose: HashSet<String>, // IDs já verificados sem .jpg antigo para importar
+}
+
+impl PackStore {
+    fn open(dir: &Path) -> io::Result<Self> {
+        let index_path = dir.join(INDEX_FILE);
+        let mut index: PackIndex = fs::read(&index_path)
+            .ok()
+            .and_then(|bytes| serde_json::from_slice(&bytes).ok())
+            .filter(|index: &PackIndex| index.version == INDEX_VERSION)
+            .unwrap_or_default();
+        index.version = INDEX_VERSION;
+
+        let pack = OpenOptions::new()
+            .read(true)
+            .append(true)
+            .create(true)
+            .open(dir.join(pack_name(index.pack)))?;
+        let pack_len = pack.metadata()?.len();
+        remove_other_packs(dir, &pack_name(index.pack));
+
+        // Descartar entradas que apontam além do fim do pacote (crash durante uma escrita)
+        let before = index.entries.len();
+        index.entries.retain(|_, entry| {
+            entry.jpeg.is_none_or(|b| b.end() <= pack_len) && entry.rgba.is_none_or(|b| b.end() <= pack_len)
+        });
+
+        let mut store = Self {
+            dir: dir.to_path_buf(),
+            pack,
+            pack_len,
+            dirty: index.entries.len() != before,
+            index,
+            missing_loose: HashSet::new(),
+        };
+
+        if store.index.garbage > COMPACT_MIN_GARBAGE && store.index.garbage * 2 > store.pack_len {
+            store.compact()?;
+        }
+
+        Ok(store)
+    }
+
+    fn read_blob(&mut self, blob: Blob) -> io::Result<Vec<u8>> {
+        let mut buffer = vec![0; blob.len as usize];
+        self.pack.seek(SeekFrom::Start(blob.offset))?;
+        self.pack.read_exact(&mut buffer)?;
+        Ok(buffer)
+    }
+
+    fn append_blob(&mut self, bytes: &[u8]) -> io::Result<Blob> {
+        // O arquivo está em modo append, então a escrita sempre vai para o fim. Se ela falhar no
+        // meio, cortar o que foi escrito para as próximas posições continuarem certas.
+        if let Err(e) = self.pack.write_all(bytes) {
+            let _ = self.pack.set_len(self.pack_len);
+            return Err(e);
+        }
+        let blob = Blob {
+            offset: self.pack_len,
+            len: bytes.len() as u64,
+        };
+        self.pack_len += blob.len;
+        Ok(blob)
+    }
+
+    fn is_packed(&self, id: &str) -> bool {
+        self.index.entries.get(id).is_some_and(|e| e.rgba.is_some())
+    }
+
+    // Há um <id>.jpg do formato Pasta ainda não importado? Ele é importado quando a imagem é
+    // carregada (load_images), para não baixar de novo o que já existe.
+    fn has_loose(&mut self, id: &str) -> bool {
+        if self.missing_loose.contains(id) {
+            return false;
+        }
+        let exists = loose_path(&self.dir, id).exists();
+        if !exists {
+            self.missing_loose.insert(id.to_string());
+        }
+        exists
+    }
+
+    // Reescreve o pacote só com os trechos ainda usados, num arquivo novo. O índice em memória só
+    // muda depois que tudo deu certo; o índice no disco passa a apontar para o pacote novo
+    // (write_atomic) e só então o antigo é apagado.
+    fn compact(&mut self) -> io::Result<()> {
+        let number = self.index.pack + 1;
+        let new_path = self.dir.join(pack_name(number));
+        let mut new_pack = File::create(&new_path)?;
+        let mut new_len = 0u64;
+        let mut entries = self.index.entries.clone();
+
+        for entry in entries.values_mut() {
+            for blob in [&mut entry.jpeg, &mut entry.rgba].into_iter().flatten() {
+                let bytes = self.read_blob(*blob)?;
+                new_pack.write_all(&bytes)?;
+                *blob = Blob {
+                    offset: new_len,
+                    len: blob.len,
+                };
+                new_len += blob.len;
+            }
+        }
+        new_pack.sync_all()?;
+        drop(new_pack);
+
+        let index = PackIndex {
+            version: INDEX_VERSION,
+            pack: number,
+            garbage: 0,
+            entries,
+        };
+        write_atomic(&self.dir.join(INDEX_FILE), &serde_json::to_vec(&index)?)?;
+
+        let old_path = self.dir.join(pack_name(self.index.pack));
+        self.pack = OpenOptions::new().read(true).append(true).open(&new_path)?;
+        self.pack_len = new_len;
+        self.index = index;
+        self.dirty = false;
+        let _ = fs::remove_file(old_path);
+        Ok(())
+    }
+}
+
+impl ThumbStore for PackStore {
+    fn contains(&mut self, id: &str) -> bool {
+        self.is_packed(id) || self.has_loose(id)
+    }
+
+    fn image_source(&mut self, id: &str) -> Option<ImageSource> {
+        match self.index.entries.get(id) {
+            Some(&PackEntry { rgba: Some(blob), width, height, .. }) => Some(ImageSource::Pack {
+                path: self.dir.join(pack_name(self.index.pack)),
+                blob,
+                width,
+                height,
+            }),
+            _ => self.has_loose(id).then(|| ImageSource::Import(loose_path(&self.dir, id))),
+        }
+    }
+
+    fn import_loose(&mut self, id: &str, thumbnail: &Thumbnail, meta: ThumbMeta) {
+        // Um download pode ter trazido a thumbnail enquanto o .jpg era decodificado
+        if !self.is_packed(id) {
+            let _ = self.insert(id, thumbnail, meta);
+        }
+    }
+
+    fn insert(&mut self, id: &str, thumbnail: &Thumbnail, meta: ThumbMeta) -> io::Result<()> {
+        let jpeg = self.append_blob(&thumbnail.bytes)?;
+        let rgba = self.append_blob(&thumbnail.rgba)?;
+
+        let old = self.index.entries.insert(
+            id.to_string(),
+            PackEntry {
+                jpeg: Some(jpeg),
+                rgba: Some(rgba),
+                width: thumbnail.width,
+                height: thumbnail.height,
+                meta,
+            },
+        );
+        if let Some(old) = old {
+            self.index.garbage += [old.jpeg, old.rgba].iter().flatten().map(|b| b.len).sum::<u64>();
+        }
+        self.dirty = true;
+        Ok(())
+    }
+
+    fn meta(&mut self, id: &str) -> Option<ThumbMeta> {
+        if self.is_packed(id) {
+            return self.index.entries.get(id).map(|e| e.meta.clone());
+        }
+        self.has_loose(id).then(|| loose_meta(&loose_path(&self.dir, id)))
+    }
+
+    fn set_meta(&mut self, id: &str, meta: ThumbMeta) {
+        if let Some(entry) = self.index.entries.get_mut(id) {
+            entry.meta = meta;
+            self.dirty = true;
+        }
+    }
+
+    fn mark_failed(&mut self, id: &str) {
+        let entry = self.index.entries.entry(id.to_string()).or_default();
+        entry.meta.failures += 1;
+        entry.meta.last_failure = now_secs();
+        self.dirty = true;
+    }
+
+    fn is_failed(&self, id: &str) -> bool {
+        self.index.entries.get(id).is_some_and(|e| e.meta.is_failed())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        if !self.dirty {
+            return Ok(());
+        }
+        // Garantir que os bytes estão no disco antes de o índice apontar para eles
+        self.pack.sync_data()?;
+        let json = serde_json::to_vec(&self.index)?;
+        write_atomic(&self.dir.join(INDEX_FILE), &json)?;
+        self.dirty = false;
+        Ok(())
+    }
+
+    fn summary(&self) -> String {
+        let count = self.index.entries.values().filter(|e| e.rgba.is_some()).count();
+        format!(
+            "{} thumbnails, {:.1} MB em {} ({:.1} MB não usados)",
+            count,
+            self.pack_len as f64 / 1_048_576.0,
+            pack_name(self.index.pack),
+            self.index.garbage as f64 / 1_048_576.0,
+        )
+    }
+}
+
+// Informações de um .jpg baixado antes de existir thumbs_meta.json: a data do arquivo
+fn loose_meta(path: &Path) -> ThumbMeta {
+    ThumbMeta {
+        fetched_at: mtime_secs(path).unwrap_or_else(now_secs),
+        ..Default::default()
+    }
+}
+
+// Apaga pacotes que o índice não usa mais (sobras de uma compactação interrompida)
+fn remove_other_packs(dir: &Path, current: &str) {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return;
+    };
+    for entry in entries.flatten() {
+        let name = entry.file_name().to_string_lossy().into_owned();
+        let is_pack = name.starts_with("thumbs.") && (name.ends_with(".pack") || name.ends_with(".pack.tmp"));
+        if is_pack && name != current {
+            let _ = fs::remove_file(entry.path());
+        }
+    }
+}
+
+impl Drop for DirStore {
+    fn drop(&mut self) {
+        let _ = self.flush();
+    }
+}
+
+impl Drop for PackStore {
+    fn drop(&mut self) {
+        let _ = self.flush();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // PNG 2x2 válido
+    fn png() -> Vec<u8> {
+        let mut bytes = Vec::new();
+        image::RgbaImage::new(2, 2)
+            .write_to(&mut io::Cursor::new(&mut bytes), image::ImageFormat::Png)
+            .unwrap();
+        bytes
+    }
+
+    fn loads(store: &mut dyn ThumbStore, id: &str) -> bool {
+        store.image_source(id).and_then(ImageSource::read).is_some()
+    }
+
+    fn meta(fetched_at: u64, max_age: Option<u64>) -> ThumbMeta {
+        ThumbMeta {
+            fetched_at,
+            max_age,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn max_age_do_servidor_prevalece() {
+        let hour = 3600;
+        // no-cache: revalida sempre, mesmo com intervalo mínimo configurado
+        assert!(meta(1000, Some(0)).is_stale_at(24 * hour, 1000));
+        // max-age curto vence o intervalo mínimo
+        assert!(meta(1000, Some(60)).is_stale_at(24 * hour, 1060));
+        assert!(!meta(1000, Some(60)).is_stale_at(24 * hour, 1059));
+        // max-age longo também
+        assert!(!meta(0, Some(48 * hour)).is_stale_at(24 * hour, 30 * hour));
+        // Sem Cache-Control: intervalo mínimo
+        assert!(!meta(0, None).is_stale_at(24 * hour, 23 * hour));
+        assert!(meta(0, None).is_stale_at(24 * hour, 24 * hour));
+    }
+
+    #[test]
+    fn jpg_da_pasta_e_importado_ao_carregar() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::write(loose_path(dir.path(), "a"), png()).unwrap();
+        let mut cache = ThumbCache::in_dir(dir.path()).unwrap();
+        assert!(!cache.needs_download("a")); // Só verifica o arquivo, sem decodificar
+        assert!(!cache.needs_download("a"));
+        assert!(cache.needs_download("b"));
+        assert!(cache.load_image("a").is_some());
+        cache.flush();
+        drop(cache);
+
+        fs::remove_file(loose_path(dir.path(), "a")).unwrap();
+        let mut store = PackStore::open(dir.path()).unwrap();
+        assert!(store.is_packed("a"));
+        assert!(matches!(store.image_source("a"), Some(ImageSource::Pack { .. })));
+    }
+
+    // Abre o cache e lê as thumbnails de uma playlist grande nos dois formatos, como na
+    // inicialização. Para ver os tempos:
+    // cargo test --release tempo_de_abertura -- --ignored --nocapture
+    #[test]
+    #[ignore]
+    fn tempo_de_abertura_pasta_e_pacote() {
+        const COUNT: usize = 1000;
+        // JPEG do tamanho das thumbnails do YouTube (hqdefault), com ruído para não comprimir demais
+        let mut seed = 1u32;
+        let image = image::RgbImage::from_fn(480, 360, |x, y| {
+            seed = seed.wrapping_mul(1_664_525).wrapping_add(1_013_904_223);
+            let noise = (seed >> 24) as u8 / 4;
+            image::Rgb([(x / 2) as u8 ^ noise, (y / 2) as u8, ((x + y) / 4) as u8 ^ noise])
+        });
+        let mut jpeg = Vec::new();
+        image.write_to(&mut io::Cursor::new(&mut jpeg), image::ImageFormat::Jpeg).unwrap();
+        let thumbnail = Thumbnail::decode(jpeg).unwrap();
+        let ids: Vec<String> = (0..COUNT).map(|i| format!("video{:04}", i)).collect();
+
+        let dir_cache = tempfile::tempdir().unwrap();
+        let pack_cache = tempfile::tempdir().unwrap();
+        {
+            let mut dir_store = DirStore::open(dir_cache.path());
+            let mut pack_store = PackStore::open(pack_cache.path()).unwrap();
+            for id in &ids {
+                dir_store.insert(id, &thumbnail, ThumbMeta::default()).unwrap();
+                pack_store.insert(id, &thumbnail, ThumbMeta::default()).unwrap();
+            }
+        }
+
+        // O que a interface faz ao abrir: procurar cada vídeo no cache e carregar a imagem
+        let measure = |open: &dyn Fn() -> Box<dyn ThumbStore>| {
+            let start = std::time::Instant::now();
+            let mut store = open();
+            for id in &ids {
+                assert!(store.contains(id));
+                assert!(matches!(store.image_source(id).and_then(ImageSource::read), Some(LoadedImage::Ready(_))));
+            }
+            start.elapsed()
+        };
+        let dir_time = measure(&|| Box::new(DirStore::open(dir_cache.path())));
+        let pack_time = measure(&|| Box::new(PackStore::open(pack_cache.path()).unwrap()));
+        println!(
+            "{} thumbnails: pasta {:.0} ms, pacote {:.0} ms ({:.1}x)",
+            COUNT,
+            dir_time.as_secs_f64() * 1000.0,
+            pack_time.as_secs_f64() * 1000.0,
+            dir_time.as_secs_f64() / pack_time.as_secs_f64(),
+        );
+        assert!(pack_time < dir_time);
+    }
+
+    #[test]
+    fn compactar_troca_o_pacote_e_mantem_as_thumbnails() {
+        let dir = tempfile::tempdir().unwrap();
+        let thumbnail = Thumbnail::decode(png()).unwrap();
+        {
+            let mut store = PackStore::open(dir.path()).unwrap();
+            store.insert("a", &thumbnail, ThumbMeta::default()).unwrap();
+            store.insert("b", &thumbnail, ThumbMeta::default()).unwrap();
+            store.insert("a", &thumbnail, ThumbMeta::default()).unwrap(); // Deixa lixo
+            store.compact().unwrap();
+            assert_eq!(store.index.garbage, 0);
+            assert!(loads(&mut store, "a"));
+        }
+        assert!(!dir.path().join(PACK_FILE).exists());
+        assert!(dir.path().join(pack_name(1)).exists());
+
+        let mut store = PackStore::open(dir.path()).unwrap();
+        assert_eq!(store.index.pack, 1);
+        assert!(loads(&mut store, "a"));
+        assert!(loads(&mut store, "b"));
+    }
+}
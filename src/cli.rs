@@ -0,0 +1,103 @@
+// Linha de comando: m3u8-GUI --query "<consulta>" <playlist.m3u>
+// Imprime em M3U as entradas da playlist que atendem a consulta, com a mesma linguagem da
+// barra de pesquisa. As tags vêm do m3u.json ao lado da playlist e as reproduções do
+// play_history.json.
+use crate::m3u;
+use crate::metadata::{self, Metadata};
+use crate::play_history::PlayHistory;
+use crate::query::{Item, Query, Searchable};
+use crate::util;
+use std::fs::File;
+use std::io::{self, BufReader, Write};
+use std::path::Path;
+
+const USAGE: &str = "Uso: m3u8-GUI --query \"<consulta>\" <playlist.m3u>";
+
+// Some(código de saída) quando os argumentos pedem o modo linha de comando; None abre a janela
+pub fn run(args: &[String]) -> Option<i32> {
+    let flag = args.iter().position(|a| a == "--query" || a == "-q")?;
+    attach_console();
+    let query_text = args.get(flag + 1);
+    let path = args
+        .iter()
+        .enumerate()
+        .skip(1)
+        .find(|&(i, _)| i != flag && i != flag + 1)
+        .map(|(_, a)| a);
+    let (Some(query_text), Some(path)) = (query_text, path) else {
+        eprintln!("{}", USAGE);
+        return Some(2);
+    };
+
+    match filter(query_text, Path::new(path)) {
+        Ok(()) => Some(0),
+        Err(e) => {
+            eprintln!("{}", e);
+            Some(1)
+        }
+    }
+}
+
+// Com #![windows_subsystem = "windows"] o programa não ganha console: usar o do terminal que o
+// abriu. Saída redirecionada (> arquivo.m3u) já chega pronta e não é trocada.
+#[cfg(windows)]
+fn attach_console() {
+    const ATTACH_PARENT_PROCESS: u32 = u32::MAX;
+    const STD_OUTPUT_HANDLE: u32 = -11i32 as u32;
+    #[link(name = "kernel32")]
+    extern "system" {
+        fn AttachConsole(process_id: u32) -> i32;
+        fn GetStdHandle(std_handle: u32) -> *mut std::ffi::c_void;
+    }
+    unsafe {
+        if GetStdHandle(STD_OUTPUT_HANDLE).is_null() {
+            AttachConsole(ATTACH_PARENT_PROCESS);
+        }
+    }
+}
+
+#[cfg(not(windows))]
+fn attach_console() {}
+
+fn filter(query_text: &str, path: &Path) -> Result<(), String> {
+    let query = Query::parse(query_text).map_err(|e| {
+        // Consulta com ^ embaixo do ponto do erro
+        let column = query_text[..e.position.min(query_text.len())].chars().count();
+        format!("Consulta inválida: {}\n  {}\n  {}^", e, query_text, " ".repeat(column))
+    })?;
+    let file = File::open(path).map_err(|e| format!("Erro ao abrir {}: {}", path.display(), e))?;
+    let playlist = m3u::parse_playlist(BufReader::new(file));
+    let metadata = Metadata::load(&metadata::path_for(path)).unwrap_or_else(|e| {
+        eprintln!("{}", e);
+        Metadata::default()
+    });
+
+    let history = PlayHistory::load().unwrap_or_else(|e| {
+        eprintln!("{}", e);
+        PlayHistory::read_only()
+    });
+    let now = util::now_secs();
+
+    let mut out = String::new();
+    for line in &playlist.header {
+        out.push_str(line);
+        out.push('\n');
+    }
+    for entry in &playlist.entries {
+        let searchable = Searchable::new(&entry.title, &entry.url, entry.group.as_deref(), entry.duration, &entry.id);
+        let item = Item {
+            entry: &searchable,
+            metadata: &metadata,
+            play_count: history.play_count(&entry.id),
+            played_days_ago: history.stats().get(&entry.id).map(|s| s.days_ago(now)),
+            selected: false,
+            base_dir: path.parent(),
+        };
+        if query.matches(&item) {
+            m3u::write_entry_preserving(&mut out, &entry.to_write());
+        }
+    }
+    io::stdout()
+        .write_all(out.as_bytes())
+        .map_err(|e| format!("Erro ao escrever a saída: {}", e))
+}
@@ -0,0 +1,60 @@
+// Área de transferência com dois formatos ao mesmo tempo: texto simples com as URLs (para chats
+// e navegadores) e HTML com o fragmento #EXTM3U, que o Ctrl+V do app usa para manter títulos,
+// grupos e opções HTTP ao mover vídeos entre playlists.
+const MARKER: &str = "data-m3u8-gui";
+
+#[derive(Default)]
+pub struct Clipboard {
+    inner: Option<arboard::Clipboard>, // Aberta no primeiro uso e mantida (no X11 o conteúdo some com ela)
+}
+
+impl Clipboard {
+    fn open(&mut self) -> Result<&mut arboard::Clipboard, String> {
+        if self.inner.is_none() {
+            let clipboard = arboard::Clipboard::new().map_err(|e| format!("Não foi possível abrir a área de transferência: {}", e))?;
+            self.inner = Some(clipboard);
+        }
+        Ok(self.inner.as_mut().unwrap())
+    }
+
+    // Copia as URLs como texto e o fragmento M3U como HTML
+    pub fn set(&mut self, urls: &str, m3u: &str) -> Result<(), String> {
+        self.open()?
+            .set_html(to_html(m3u), Some(urls.to_string()))
+            .map_err(|e| format!("Não foi possível copiar: {}", e))
+    }
+
+    // Fragmento M3U copiado pelo app junto com o texto colado, se o texto ainda for o nosso
+    pub fn m3u_for(&mut self, pasted: &str) -> Option<String> {
+        let html = self.open().ok()?.get().html().ok()?;
+        let m3u = from_html(&html)?;
+        let urls: Vec<String> = crate::m3u::parse(m3u.as_bytes()).into_iter().map(|e| e.url).collect();
+        (urls.join("\n") == pasted.replace("\r\n", "\n").trim()).then_some(m3u)
+    }
+}
+
+fn to_html(m3u: &str) -> String {
+    let escaped = m3u.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;");
+    format!("<pre {}>{}</pre>", MARKER, escaped)
+}
+
+fn from_html(html: &str) -> Option<String> {
+    let start = html.find(&format!("<pre {}>", MARKER))? + MARKER.len() + 6;
+    let end = start + html[start..].find("</pre>")?;
+    Some(html[start..end].replace("&lt;", "<").replace("&gt;", ">").replace("&amp;", "&"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn html_ida_e_volta() {
+        let m3u = "#EXTM3U\n#EXTINF:-1 group-title=\"A & B\",<Título>\nhttps://a/1?x=1&y=2\n";
+        assert_eq!(from_html(&to_html(m3u)).as_deref(), Some(m3u));
+        // Outros programas envolvem o HTML copiado
+        let wrapped = format!("<html><body>{}</body></html>", to_html(m3u));
+        assert_eq!(from_html(&wrapped).as_deref(), Some(m3u));
+        assert_eq!(from_html("<pre>#EXTM3U</pre>"), None);
+    }
+}
@@ -0,0 +1,231 @@
+// Download de thumbnails em segundo plano, com revalidação condicional (ETag / Last-Modified)
+use crate::cache::{SharedCache, ThumbMeta, Thumbnail};
+use crate::util::now_secs;
+use reqwest::header::{self, HeaderMap};
+use reqwest::{Client, StatusCode};
+use std::collections::HashSet;
+
+// Uma thumbnail a ser baixada ou revalidada
+pub struct DownloadJob {
+    pub id: String,
+    pub url: String,
+    pub headers: Vec<(String, String)>, // Cabeçalhos pedidos pela entrada (#EXTVLCOPT / #EXTHTTP)
+}
+
+// IDs que esperam download, na ordem em que foram pedidos. O conjunto evita percorrer a fila a
+// cada vídeo ao enfileirar playlists grandes.
+#[derive(Default)]
+pub struct DownloadQueue {
+    order: Vec<String>,
+    queued: HashSet<String>,
+}
+
+impl DownloadQueue {
+    pub fn contains(&self, id: &str) -> bool {
+        self.queued.contains(id)
+    }
+
+    pub fn push(&mut self, id: &str) {
+        if self.queued.insert(id.to_string()) {
+            self.order.push(id.to_string());
+        }
+    }
+
+    // Retira os primeiros `max` IDs da fila
+    pub fn take(&mut self, max: usize) -> Vec<String> {
+        let ids: Vec<String> = self.order.drain(..max.min(self.order.len())).collect();
+        for id in &ids {
+            self.queued.remove(id);
+        }
+        ids
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.order.is_empty()
+    }
+
+    pub fn clear(&mut self) {
+        self.order.clear();
+        self.queued.clear();
+    }
+}
+
+// Baixa um lote de thumbnails em sequência, gravando cada uma no cache assim que chega
+pub async fn run_batch(client: Client, cache: SharedCache, jobs: Vec<DownloadJob>, ctx: egui::Context) {
+    for job in jobs {
+        // Se a thumbnail já está no cache, enviar os validadores para receber 304 se não mudou
+        let cached = cache.lock().unwrap().meta(&job.id);
+        let mut request = client.get(&job.url);
+        for (name, value) in &job.headers {
+            request = request.header(name.as_str(), value.as_str());
+        }
+        if let Some(meta) = &cached {
+            if let Some(etag) = &meta.etag {
+                request = request.header(header::IF_NONE_MATCH, etag);
+            }
+            if let Some(last_modified) = &meta.last_modified {
+                request = request.header(header::IF_MODIFIED_SINCE, last_modified);
+            }
+        }
+
+        match request.send().await {
+            Ok(response) if response.status() == StatusCode::NOT_MODIFIED => {
+                let mut meta = meta_from_headers(response.headers());
+                // O 304 pode omitir os validadores; manter os que já tínhamos
+                if let Some(old) = cached {
+                    meta.etag = meta.etag.or(old.etag);
+                    meta.last_modified = meta.last_modified.or(old.last_modified);
+                }
+                cache.lock().unwrap().touch(&job.id, meta);
+            }
+            Ok(response) if response.status().is_success() => {
+                let meta = meta_from_headers(response.headers());
+                if let Ok(bytes) = response.bytes().await {
+                    // Decodificar fora do lock do cache, que a interface usa a cada quadro
+                    let bytes = bytes.to_vec();
+                    match tokio::task::spawn_blocking(move || Thumbnail::decode(bytes)).await {
+                        Ok(Ok(thumbnail)) => {
+                            cache.lock().unwrap().insert(&job.id, &thumbnail, meta);
+                            ctx.request_repaint();
+                        }
+                        // Não é uma imagem (ex.: página de erro com status 200)
+                        _ => cache.lock().unwrap().mark_failed(&job.id),
+                    }
+                }
+            }
+            // Resposta de erro (ex.: 404 para vídeos que não são do YouTube)
+            Ok(_) => cache.lock().unwrap().mark_failed(&job.id),
+            // Erro de rede: tentar de novo na próxima abertura
+            Err(_) => {}
+        }
+    }
+    cache.lock().unwrap().flush();
+}
+
+// Extrai validadores e max-age dos cabeçalhos da resposta
+fn meta_from_headers(headers: &HeaderMap) -> ThumbMeta {
+    let text = |name| {
+        headers
+            .get(name)
+            .and_then(|v| v.to_str().ok())
+            .map(str::to_string)
+    };
+    ThumbMeta {
+        fetched_at: now_secs(),
+        etag: text(header::ETAG),
+        last_modified: text(header::LAST_MODIFIED),
+        max_age: text(header::CACHE_CONTROL).and_then(|v| parse_max_age(&v)),
+        ..Default::default()
+    }
+}
+
+// Lê o max-age do Cache-Control; no-cache e no-store pedem revalidação sempre
+fn parse_max_age(cache_control: &str) -> Option<u64> {
+    let mut max_age = None;
+    for directive in cache_control.split(',').map(str::trim) {
+        let directive = directive.to_ascii_lowercase();
+        if directive == "no-cache" || directive == "no-store" {
+            return Some(0);
+        }
+        if let Some(value) = directive.strip_prefix("max-age=") {
+            max_age = value.trim_matches('"').parse().ok();
+        }
+    }
+    max_age
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cache::ThumbCache;
+    use std::io::{Read, Write};
+    use std::net::TcpListener;
+    use std::sync::{Arc, Mutex};
+
+    #[test]
+    fn fila_nao_repete_ids() {
+        let mut queue = DownloadQueue::default();
+        for id in ["a", "b", "a", "c"] {
+            queue.push(id);
+        }
+        assert_eq!(queue.take(2), ["a", "b"]);
+        assert!(!queue.contains("a"));
+        queue.push("a");
+        assert_eq!(queue.take(50), ["c", "a"]);
+        assert!(queue.is_empty());
+    }
+
+    #[test]
+    fn max_age() {
+        assert_eq!(parse_max_age("public, max-age=3600"), Some(3600));
+        assert_eq!(parse_max_age("max-age=60, no-cache"), Some(0));
+        assert_eq!(parse_max_age("No-Store"), Some(0));
+        assert_eq!(parse_max_age("public"), None);
+    }
+
+    fn png() -> Vec<u8> {
+        let mut bytes = Vec::new();
+        image::RgbaImage::new(2, 2)
+            .write_to(&mut std::io::Cursor::new(&mut bytes), image::ImageFormat::Png)
+            .unwrap();
+        bytes
+    }
+
+    // Servidor local que responde 304 a uma requisição e devolve o texto dela
+    fn serve_304() -> (String, std::thread::JoinHandle<String>) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}/thumb.jpg", listener.local_addr().unwrap());
+        let server = std::thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+            let mut request = Vec::new();
+            let mut buffer = [0; 1024];
+            while !request.ends_with(b"\r\n\r\n") {
+                let n = stream.read(&mut buffer).unwrap();
+                if n == 0 {
+                    break;
+                }
+                request.extend_from_slice(&buffer[..n]);
+            }
+            stream
+                .write_all(b"HTTP/1.1 304 Not Modified\r\nCache-Control: max-age=60\r\nContent-Length: 0\r\nConnection: close\r\n\r\n")
+                .unwrap();
+            String::from_utf8_lossy(&request).to_lowercase()
+        });
+        (url, server)
+    }
+
+    #[tokio::test]
+    async fn revalidacao_com_304_mantem_a_thumbnail() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut cache = ThumbCache::in_dir(dir.path()).unwrap();
+        let thumbnail = Thumbnail::decode(png()).unwrap();
+        let old = ThumbMeta {
+            fetched_at: 1,
+            etag: Some("\"abc\"".to_string()),
+            ..Default::default()
+        };
+        cache.insert("x", &thumbnail, old);
+        let generation = cache.generation();
+        let cache = Arc::new(Mutex::new(cache));
+
+        let (url, server) = serve_304();
+        let job = DownloadJob {
+            id: "x".to_string(),
+            url,
+            headers: vec![("Referer".to_string(), "http://site/".to_string())],
+        };
+        run_batch(Client::new(), Arc::clone(&cache), vec![job], egui::Context::default()).await;
+
+        let request = server.join().unwrap();
+        assert!(request.contains("if-none-match: \"abc\""));
+        assert!(request.contains("referer: http://site/"));
+
+        let mut cache = cache.lock().unwrap();
+        let meta = cache.meta("x").unwrap();
+        assert_eq!(meta.etag.as_deref(), Some("\"abc\"")); // O 304 omitiu o ETag
+        assert_eq!(meta.max_age, Some(60));
+        assert!(meta.fetched_at > 1);
+        assert_eq!(cache.generation(), generation); // Nada foi baixado de novo
+        assert!(cache.load_image("x").is_some());
+    }
+}
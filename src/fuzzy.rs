@@ -0,0 +1,226 @@
+// Pesquisa aproximada no estilo do fzf: as letras da consulta aparecem em ordem no título, com
+// pontos extras para letras seguidas e para começos de palavra, e desconto para os buracos.
+// Tolera alguns erros de digitação (letra errada, faltando ou sobrando, duas letras invertidas):
+// 1 erro a partir de 4 letras, 2 a partir de 8.
+use crate::text::Folded;
+use std::cell::RefCell;
+
+const MATCH: i32 = 16;
+const CONSECUTIVE: i32 = 8; // Letra logo depois da anterior
+const BOUNDARY: i32 = 8;    // Letra no começo de uma palavra
+const GAP: i32 = -2;        // Cada letra do título pulada entre duas encontradas
+const TYPO: i32 = -16;
+const NONE: i32 = i32::MIN / 4;
+
+pub struct Match {
+    pub score: i32,
+    pub positions: Vec<usize>, // Letras do texto original que casaram, em ordem
+}
+
+fn max_typos(pattern_len: usize) -> usize {
+    match pattern_len {
+        0..=3 => 0,
+        4..=7 => 1,
+        _ => 2,
+    }
+}
+
+// Tabelas reaproveitadas entre um título e outro (uma pesquisa passa por milhares deles)
+#[derive(Default)]
+struct Scratch {
+    aligned: Vec<i32>,    // Melhor pontuação com a letra i da consulta casada com a letra j do título
+    best: Vec<i32>,       // Melhor pontuação com as i primeiras letras resolvidas até a letra j
+    aligned_from: Vec<u8>,
+    best_from: Vec<u8>,
+}
+
+thread_local! {
+    static SCRATCH: RefCell<Scratch> = RefCell::new(Scratch::default());
+}
+
+// Casa `pattern` (já dobrado, ver text::Folded) com o texto; None se ficar longe demais
+pub fn find(pattern: &[char], text: &Folded) -> Option<Match> {
+    let m = pattern.len();
+    let n = text.chars.len();
+    if m == 0 {
+        return Some(Match { score: 0, positions: Vec::new() });
+    }
+    let typos = max_typos(m);
+    // Descarte rápido: letras da consulta que nem existem no título já são erros
+    let absent = pattern.iter().filter(|c| !text.chars.contains(c)).count();
+    if absent > typos || n + typos < m {
+        return None;
+    }
+
+    SCRATCH.with(|scratch| {
+        let mut scratch = scratch.borrow_mut();
+        let scratch = &mut *scratch;
+        let width = n + 1;
+        let size = (typos + 1) * (m + 1) * width;
+        let index = |k: usize, i: usize, j: usize| (k * (m + 1) + i) * width + j;
+        for table in [&mut scratch.aligned, &mut scratch.best] {
+            table.clear();
+            table.resize(size, NONE);
+        }
+        scratch.aligned_from.clear();
+        scratch.aligned_from.resize(size, 0);
+        scratch.best_from.clear();
+        scratch.best_from.resize(size, 0);
+        let Scratch { aligned, best, aligned_from, best_from } = scratch;
+
+        for k in 0..=typos {
+            for j in 0..=n {
+                best[index(k, 0, j)] = 0; // O começo do título pode ser pulado de graça
+            }
+        }
+
+        for k in 0..=typos {
+            for i in 1..=m {
+                let q = pattern[i - 1];
+                for j in 0..=n {
+                    if j >= 1 {
+                        let t = text.chars[j - 1];
+                        let (mut value, mut from) = (NONE, 0);
+                        if q == t {
+                            let bonus = if j == 1 || !text.chars[j - 2].is_alphanumeric() { BOUNDARY } else { 0 };
+                            let after_gap = best[index(k, i - 1, j - 1)];
+                            if after_gap > NONE {
+                                (value, from) = (after_gap + MATCH + bonus, 1);
+                            }
+                            let after_match = aligned[index(k, i - 1, j - 1)];
+                            if after_match > NONE && after_match + MATCH + bonus + CONSECUTIVE > value {
+                                (value, from) = (after_match + MATCH + bonus + CONSECUTIVE, 2);
+                            }
+                        } else if k >= 1 {
+                            // Letra errada
+                            let previous = best[index(k - 1, i - 1, j - 1)];
+                            if previous > NONE {
+                                (value, from) = (previous + TYPO, 3);
+                            }
+                        }
+                        // Duas letras invertidas ("cacno" por "canco")
+                        if k >= 1 && i >= 2 && j >= 2 && q != pattern[i - 2] && q == text.chars[j - 2] && pattern[i - 2] == t {
+                            let previous = best[index(k - 1, i - 2, j - 2)];
+                            if previous > NONE && previous + 2 * MATCH + TYPO > value {
+                                (value, from) = (previous + 2 * MATCH + TYPO, 4);
+                            }
+                        }
+                        aligned[index(k, i, j)] = value;
+                        aligned_from[index(k, i, j)] = from;
+                    }
+
+                    let (mut value, mut from) = (aligned[index(k, i, j)], 0);
+                    if j >= 1 {
+                        let gap = best[index(k, i, j - 1)];
+                        if gap > NONE && gap + GAP > value {
+                            (value, from) = (gap + GAP, 1);
+                        }
+                    }
+                    if k >= 1 {
+                        // Letra da consulta que não está no título
+                        let skip = best[index(k - 1, i - 1, j)];
+                        if skip > NONE && skip + TYPO > value {
+                            (value, from) = (skip + TYPO, 2);
+                        }
+                    }
+                    best[index(k, i, j)] = value;
+                    best_from[index(k, i, j)] = from;
+                }
+            }
+        }
+
+        // Melhor final, preferindo menos erros no empate
+        let (mut end_k, mut end_j, mut score) = (0, 0, NONE);
+        for k in 0..=typos {
+            for j in 0..=n {
+                if best[index(k, m, j)] > score {
+                    (end_k, end_j, score) = (k, j, best[index(k, m, j)]);
+                }
+            }
+        }
+        // Letras espalhadas demais (ou erros demais) não contam como resultado
+        if score < MATCH * m as i32 / 2 {
+            return None;
+        }
+
+        // Refazer o caminho para saber quais letras casaram
+        let mut positions = Vec::new();
+        let (mut k, mut i, mut j) = (end_k, m, end_j);
+        let mut in_best = true;
+        while i > 0 {
+            if in_best {
+                match best_from[index(k, i, j)] {
+                    0 => in_best = false,
+                    1 => j -= 1,
+                    _ => (k, i) = (k - 1, i - 1),
+                }
+                continue;
+            }
+            match aligned_from[index(k, i, j)] {
+                1 => {
+                    positions.push(j - 1);
+                    (i, j, in_best) = (i - 1, j - 1, true);
+                }
+                2 => {
+                    positions.push(j - 1);
+                    (i, j) = (i - 1, j - 1);
+                }
+                3 => (k, i, j, in_best) = (k - 1, i - 1, j - 1, true),
+                _ => {
+                    positions.push(j - 1);
+                    positions.push(j - 2);
+                    (k, i, j, in_best) = (k - 1, i - 2, j - 2, true);
+                }
+            }
+        }
+        let mut positions: Vec<usize> = positions.into_iter().map(|p| text.origin[p]).collect();
+        positions.sort_unstable();
+        positions.dedup();
+        Some(Match { score, positions })
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn search(pattern: &str, title: &str) -> Option<Match> {
+        let pattern: Vec<char> = crate::text::fold(pattern).chars().collect();
+        find(&pattern, &Folded::new(title))
+    }
+
+    #[test]
+    fn erros_tolerados_pelo_tamanho() {
+        // Até 3 letras nenhum erro
+        assert!(search("abc", "xabcx").is_some());
+        assert!(search("abd", "abc").is_none());
+        // De 4 a 7 letras, um erro: trocada, faltando, sobrando ou invertida
+        assert!(search("matrx", "Matrix").is_some());
+        assert!(search("matriix", "Matrix").is_some());
+        assert!(search("mtarix", "Matrix").is_some());
+        assert!(search("matrox", "Matrix").is_some());
+        assert!(search("motrox", "Matrix").is_none());
+        assert!(search("xirtam", "Matrix").is_none()); // Todas as letras existem, fora de ordem
+        // A partir de 8, dois
+        assert!(search("relodedd", "Reloaded").is_some());
+        assert!(search("relxdexd", "Reloaded").is_some());
+        assert!(search("relxdxxd", "Reloaded").is_none());
+    }
+
+    #[test]
+    fn menos_erros_pontua_mais() {
+        let exact = search("matrix", "Matrix").unwrap().score;
+        let typo = search("matrox", "Matrix").unwrap().score;
+        assert!(exact > typo);
+        // Letras seguidas valem mais que espalhadas
+        assert!(search("cao", "Canção").unwrap().score > search("cao", "Casa do Outro").unwrap().score);
+    }
+
+    #[test]
+    fn posicoes_no_titulo_original() {
+        assert_eq!(search("acao", "Ação").unwrap().positions, [0, 1, 2, 3]);
+        assert_eq!(search("cao", "Canção").unwrap().positions, [3, 4, 5]);
+        assert_eq!(search("fm", "Filme Mudo").unwrap().positions, [0, 6]); // Começos de palavra
+        assert!(search("", "x").unwrap().positions.is_empty());
+    }
+}
@@ -0,0 +1,277 @@
+// Desfazer/refazer das edições da playlist e do m3u.json (padrão command)
+//
+// Cada comando aplicado devolve o comando inverso; a pilha de desfazer guarda os inversos e a de
+// refazer guarda os comandos originais. Entradas removidas ficam dentro do comando (com a textura),
+// então a memória é limitada pelo número de passos e de entradas guardadas.
+use crate::metadata::Metadata;
+use crate::VideoEntry;
+use std::collections::{HashSet, VecDeque};
+use std::sync::Arc;
+
+const MAX_STEPS: usize = 200;
+const MAX_WEIGHT: usize = 50_000; // Soma de entradas/índices guardados em todos os passos
+
+// Novo índice de cada vídeo depois do comando (None se saiu da playlist)
+pub type IndexMap = Vec<Option<usize>>;
+
+pub enum Command {
+    Insert(Vec<(usize, VideoEntry)>), // Posições finais em ordem crescente
+    Remove(Vec<usize>),               // Posições em ordem crescente
+    Reorder(Vec<usize>),              // Nova ordem: novo[i] = antigo[order[i]]
+    Update {
+        index: usize,
+        title: String,
+        url: String,
+        id: String,
+    },
+    // Playlists, tags, players e pesquisas salvas: o m3u.json antes e depois da edição
+    // (compartilhados com os passos vizinhos e com o estado atual, então não duplicam)
+    Metadata {
+        before: Arc<Metadata>,
+        after: Arc<Metadata>,
+    },
+    Group(Vec<Command>), // Vários comandos desfeitos de uma vez
+}
+
+impl Command {
+    // Aplica o comando; devolve o inverso e, se as posições mudaram, o mapa de índices
+    pub fn apply(self, videos: &mut Vec<VideoEntry>, metadata: &mut Arc<Metadata>) -> (Command, Option<IndexMap>) {
+        match self {
+            Command::Insert(items) => {
+                let old_len = videos.len();
+                let mut positions = Vec::with_capacity(items.len());
+                for (index, entry) in items {
+                    let index = index.min(videos.len());
+                    videos.insert(index, entry);
+                    positions.push(index);
+                }
+                // Os vídeos que já existiam mantêm a ordem, pulando as posições novas
+                let inserted: HashSet<usize> = positions.iter().copied().collect();
+                let mut map = Vec::with_capacity(old_len);
+                for new in (0..videos.len()).filter(|i| !inserted.contains(i)) {
+                    map.push(Some(new));
+                }
+                (Command::Remove(positions), Some(map))
+            }
+            Command::Remove(indices) => {
+                let mut removed = Vec::with_capacity(indices.len());
+                for &index in indices.iter().rev() {
+                    if index < videos.len() {
+                        removed.push((index, videos.remove(index)));
+                    }
+                }
+                removed.reverse();
+                let removed_set: HashSet<usize> = removed.iter().map(|(i, _)| *i).collect();
+
+                let old_len = videos.len() + removed.len();
+                let mut map = Vec::with_capacity(old_len);
+                let mut next = 0;
+                for old in 0..old_len {
+                    if removed_set.contains(&old) {
+                        map.push(None);
+                    } else {
+                        map.push(Some(next));
+                        next += 1;
+                    }
+                }
+                (Command::Insert(removed), Some(map))
+            }
+            Command::Reorder(order) => {
+                let mut old: Vec<Option<VideoEntry>> = std::mem::take(videos).into_iter().map(Some).collect();
+                *videos = order.iter().filter_map(|&i| old.get_mut(i).and_then(Option::take)).collect();
+                let mut map = vec![None; old.len()];
+                let mut inverse = vec![0; order.len()];
+                for (new, &previous) in order.iter().enumerate() {
+                    map[previous] = Some(new);
+                    inverse[previous] = new;
+                }
+                (Command::Reorder(inverse), Some(map))
+            }
+            Command::Update { index, title, url, id } => {
+                let Some(video) = videos.get_mut(index) else {
+                    return (Command::Group(Vec::new()), None);
+                };
+                if video.url != url {
+                    video.texture = None;
+                }
+                let inverse = Command::Update {
+                    index,
+                    title: std::mem::replace(&mut video.title, title),
+                    url: std::mem::replace(&mut video.url, url),
+                    id: std::mem::replace(&mut video.id, id),
+                };
+                (inverse, None)
+            }
+            Command::Metadata { before, after } => {
+                // Se o m3u.json mudou por fora do histórico (juntado com outro no "Salvar como"),
+                // voltar ao `after` apagaria o que entrou; o passo não faz nada
+                if Arc::ptr_eq(metadata, &before) || **metadata == *before {
+                    *metadata = Arc::clone(&after);
+                }
+                (Command::Metadata { before: after, after: before }, None)
+            }
+            Command::Group(commands) => {
+                let mut inverses = Vec::with_capacity(commands.len());
+                let mut map: Option<IndexMap> = None;
+                for command in commands {
+                    let (inverse, step_map) = command.apply(videos, metadata);
+                    inverses.push(inverse);
+                    map = match (map, step_map) {
+                        (Some(first), Some(second)) => Some(
+                            first
+                                .into_iter()
+                                .map(|i| i.and_then(|i| second.get(i).copied().flatten()))
+                                .collect(),
+                        ),
+                        (first, second) => first.or(second),
+                    };
+                }
+                inverses.reverse();
+                (Command::Group(inverses), map)
+            }
+        }
+    }
+
+    fn weight(&self) -> usize {
+        match self {
+            Command::Insert(items) => items.len(),
+            Command::Remove(indices) => indices.len(),
+            Command::Reorder(order) => order.len(),
+            Command::Update { .. } => 1,
+            Command::Metadata { before, .. } => before.len().max(1),
+            Command::Group(commands) => commands.iter().map(Command::weight).sum::<usize>().max(1),
+        }
+    }
+
+    // Muda a playlist (e não só o m3u.json, que é salvo na hora)
+    fn changes_playlist(&self) -> bool {
+        match self {
+            Command::Metadata { .. } => false,
+            Command::Group(commands) => commands.iter().any(Command::changes_playlist),
+            _ => true,
+        }
+    }
+}
+
+struct Step {
+    id: u64,
+    label: String,
+    command: Command, // Inverso na pilha de desfazer, original na de refazer
+}
+
+#[derive(Default)]
+pub struct History {
+    undo: VecDeque<Step>,
+    redo: Vec<Step>,
+    next_id: u64,
+    saved: Option<u64>, // Passo no topo quando a playlist foi salva/aberta
+}
+
+impl History {
+    // Guarda o inverso de um comando que acabou de ser aplicado
+    pub fn record(&mut self, label: String, inverse: Command) {
+        self.next_id += 1;
+        self.redo.clear();
+        self.undo.push_back(Step { id: self.next_id, label, command: inverse });
+
+        // Limite de memória: descartar os passos mais antigos
+        let mut weight: usize = self.undo.iter().map(|s| s.command.weight()).sum();
+        while self.undo.len() > 1 && (self.undo.len() > MAX_STEPS || weight > MAX_WEIGHT) {
+            if let Some(step) = self.undo.pop_front() {
+                weight -= step.command.weight();
+            }
+        }
+    }
+
+    pub fn undo(&mut self, videos: &mut Vec<VideoEntry>, metadata: &mut Arc<Metadata>) -> Option<Option<IndexMap>> {
+        let step = self.undo.pop_back()?;
+        let (command, map) = step.command.apply(videos, metadata);
+        self.redo.push(Step { command, ..step });
+        Some(map)
+    }
+
+    pub fn redo(&mut self, videos: &mut Vec<VideoEntry>, metadata: &mut Arc<Metadata>) -> Option<Option<IndexMap>> {
+        let step = self.redo.pop()?;
+        let (command, map) = step.command.apply(videos, metadata);
+        self.undo.push_back(Step { command, ..step });
+        Some(map)
+    }
+
+    pub fn clear(&mut self) {
+        self.undo.clear();
+        self.redo.clear();
+        self.saved = None;
+    }
+
+    pub fn mark_saved(&mut self) {
+        self.saved = self.last_playlist_step();
+    }
+
+    // A playlist está diferente de quando foi salva/aberta (edições só do m3u.json não contam)
+    pub fn is_modified(&self) -> bool {
+        self.last_playlist_step() != self.saved
+    }
+
+    fn last_playlist_step(&self) -> Option<u64> {
+        self.undo.iter().rev().find(|s| s.command.changes_playlist()).map(|s| s.id)
+    }
+
+    pub fn can_undo(&self) -> bool {
+        !self.undo.is_empty()
+    }
+
+    pub fn can_redo(&self) -> bool {
+        !self.redo.is_empty()
+    }
+
+    // Passos já feitos, do mais antigo ao mais recente
+    pub fn done(&self) -> impl Iterator<Item = &str> {
+        self.undo.iter().map(|s| s.label.as_str())
+    }
+
+    // Passos desfeitos, do próximo a refazer ao último
+    pub fn undone(&self) -> impl Iterator<Item = &str> {
+        self.redo.iter().rev().map(|s| s.label.as_str())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tagged(value: &str) -> Arc<Metadata> {
+        let mut metadata = Metadata::default();
+        metadata.set_tag("nota", "a", Some(value.to_string()));
+        Arc::new(metadata)
+    }
+
+    #[test]
+    fn desfaz_e_refaz_uma_tag() {
+        let mut videos = Vec::new();
+        let mut metadata = tagged("7");
+        let mut history = History::default();
+        history.mark_saved();
+
+        let command = Command::Metadata { before: Arc::clone(&metadata), after: tagged("9") };
+        let (inverse, map) = command.apply(&mut videos, &mut metadata);
+        assert!(map.is_none());
+        history.record("Editar tags".to_string(), inverse);
+        assert_eq!(metadata.tag_value("nota", "a"), Some("9"));
+        // O m3u.json é salvo na hora: a playlist não fica com alterações pendentes
+        assert!(!history.is_modified());
+
+        history.undo(&mut videos, &mut metadata).unwrap();
+        assert_eq!(metadata.tag_value("nota", "a"), Some("7"));
+        history.redo(&mut videos, &mut metadata).unwrap();
+        assert_eq!(metadata.tag_value("nota", "a"), Some("9"));
+    }
+
+    #[test]
+    fn nao_desfaz_por_cima_de_mudancas_de_fora() {
+        let mut videos = Vec::new();
+        let mut metadata = tagged("9");
+        let stale = Command::Metadata { before: tagged("8"), after: tagged("7") };
+        stale.apply(&mut videos, &mut metadata);
+        assert_eq!(metadata.tag_value("nota", "a"), Some("9"));
+    }
+}
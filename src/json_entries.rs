@@ -0,0 +1,124 @@
+// Vídeos em JSON, para copiar e colar entre playlists e outros programas:
+// [{"title": "...", "url": "...", "group": "...", "logo": "...", "duration": 212.0, "headers": {"Referer": "..."}}]
+use crate::m3u::{self, HttpOptions, M3uEntry};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::BTreeMap;
+
+#[derive(Serialize, Deserialize, Default, Debug, PartialEq)]
+pub struct JsonEntry {
+    #[serde(default)]
+    pub title: String,
+    pub url: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub group: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub logo: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub duration: Option<f64>, // Segundos
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub headers: BTreeMap<String, String>, // Incluindo User-Agent e Referer
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub vlc_options: Vec<String>, // Outros #EXTVLCOPT
+}
+
+impl JsonEntry {
+    pub fn new(title: &str, url: &str, group: Option<&str>, logo: Option<&str>, duration: Option<f64>, http: &HttpOptions) -> Self {
+        Self {
+            title: title.to_string(),
+            url: url.to_string(),
+            group: group.map(str::to_string),
+            logo: logo.map(str::to_string),
+            duration,
+            headers: http.all_headers().into_iter().collect(),
+            vlc_options: http.vlc_options.clone(),
+        }
+    }
+
+    fn into_entry(self) -> M3uEntry {
+        let mut http = HttpOptions {
+            vlc_options: self.vlc_options,
+            ..Default::default()
+        };
+        for (name, value) in self.headers {
+            http.set_header(name, value);
+        }
+        let url = self.url.trim().to_string();
+        M3uEntry {
+            title: if self.title.trim().is_empty() { m3u::fallback_title(&url) } else { self.title },
+            id: m3u::video_id(&url),
+            logo: self.logo,
+            group: self.group,
+            duration: self.duration.filter(|seconds| *seconds > 0.0),
+            http,
+            raw_lines: Vec::new(),
+            url,
+        }
+    }
+}
+
+pub fn looks_like_json(text: &str) -> bool {
+    let text = text.trim_start();
+    text.starts_with('[') || text.starts_with('{')
+}
+
+pub fn write(entries: &[JsonEntry]) -> String {
+    serde_json::to_string_pretty(entries).unwrap_or_default()
+}
+
+// Aceita uma lista ou um objeto só; itens sem URL são ignorados
+pub fn parse(text: &str) -> Vec<M3uEntry> {
+    let items = match serde_json::from_str::<Value>(text.trim()) {
+        Ok(Value::Array(items)) => items,
+        Ok(object @ Value::Object(_)) => vec![object],
+        _ => return Vec::new(),
+    };
+    items
+        .into_iter()
+        .filter_map(|item| serde_json::from_value::<JsonEntry>(item).ok())
+        .filter(|entry| !entry.url.trim().is_empty())
+        .map(JsonEntry::into_entry)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn ida_e_volta() {
+        let http = HttpOptions {
+            user_agent: Some("VLC/3.0".to_string()),
+            referrer: Some("https://site/".to_string()),
+            headers: vec![("Cookie".to_string(), "a=1".to_string())],
+            vlc_options: vec!["network-caching=1000".to_string()],
+        };
+        let entry = JsonEntry::new("Canal", "https://tv/1.m3u8", Some("Notícias"), Some("https://tv/1.png"), Some(60.0), &http);
+        let entries = parse(&write(&[entry]));
+
+        assert_eq!(entries.len(), 1);
+        let entry = &entries[0];
+        assert_eq!(entry.title, "Canal");
+        assert_eq!(entry.url, "https://tv/1.m3u8");
+        assert_eq!(entry.group.as_deref(), Some("Notícias"));
+        assert_eq!(entry.logo.as_deref(), Some("https://tv/1.png"));
+        assert_eq!(entry.duration, Some(60.0));
+        assert_eq!(entry.http, http);
+        assert_eq!(entry.id, m3u::video_id("https://tv/1.m3u8"));
+    }
+
+    #[test]
+    fn objeto_solto_e_itens_sem_url() {
+        let entries = parse(r#"{"url": "https://a/video.mp4"}"#);
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].title, "video.mp4");
+
+        let entries = parse(r#"[{"title": "sem url"}, {"url": " "}, {"url": "https://b/2", "title": "B"}]"#);
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].title, "B");
+
+        assert!(parse("[1, 2").is_empty());
+        assert!(looks_like_json("  [{}]"));
+        assert!(!looks_like_json("#EXTM3U"));
+    }
+}
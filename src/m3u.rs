@@ -0,0 +1,568 @@
+// Leitura e escrita de playlists M3U/M3U8
+use regex::Regex;
+use serde_json::{Map, Value};
+use std::io::BufRead;
+use std::sync::OnceLock;
+
+// Opções HTTP de uma entrada, vindas de #EXTVLCOPT e #EXTHTTP
+#[derive(Clone, Default, Debug, PartialEq)]
+pub struct HttpOptions {
+    pub user_agent: Option<String>,      // #EXTVLCOPT:http-user-agent=
+    pub referrer: Option<String>,        // #EXTVLCOPT:http-referrer=
+    pub headers: Vec<(String, String)>,  // Demais chaves do #EXTHTTP (ex.: cookie)
+    pub vlc_options: Vec<String>,        // Outros #EXTVLCOPT, repassados como estão
+}
+
+impl HttpOptions {
+    // Todos os cabeçalhos HTTP que esta entrada pede, incluindo User-Agent e Referer
+    pub fn all_headers(&self) -> Vec<(String, String)> {
+        let mut headers = Vec::new();
+        if let Some(user_agent) = &self.user_agent {
+            headers.push(("User-Agent".to_string(), user_agent.clone()));
+        }
+        if let Some(referrer) = &self.referrer {
+            headers.push(("Referer".to_string(), referrer.clone()));
+        }
+        headers.extend(self.headers.iter().cloned());
+        headers
+    }
+
+    fn parse_vlcopt(&mut self, option: &str) {
+        let option = option.trim();
+        if let Some(value) = option.strip_prefix("http-user-agent=") {
+            self.user_agent = Some(value.trim().to_string());
+        } else if let Some(value) = option
+            .strip_prefix("http-referrer=")
+            .or_else(|| option.strip_prefix("http-referer="))
+        {
+            self.referrer = Some(value.trim().to_string());
+        } else if !option.is_empty() {
+            self.vlc_options.push(option.to_string());
+        }
+    }
+
+    fn parse_exthttp(&mut self, json: &str) {
+        let Ok(Value::Object(map)) = serde_json::from_str::<Value>(json.trim()) else {
+            return;
+        };
+        for (key, value) in map {
+            let value = match value {
+                Value::String(s) => s,
+                other => other.to_string(),
+            };
+            self.set_header(key, value);
+        }
+    }
+
+    // User-Agent e Referer vão para os campos próprios; o resto fica em headers
+    pub fn set_header(&mut self, name: String, value: String) {
+        match name.to_ascii_lowercase().as_str() {
+            "user-agent" => self.user_agent = Some(value),
+            "referer" | "referrer" => self.referrer = Some(value),
+            _ => self.headers.push((name, value)),
+        }
+    }
+}
+
+// Uma entrada lida do arquivo
+pub struct M3uEntry {
+    pub title: String,
+    pub url: String,
+    pub id: String,           // ID do YouTube ou hash MD5 da URL
+    pub logo: Option<String>, // Atributo tvg-logo do #EXTINF
+    pub group: Option<String>, // Atributo group-title do #EXTINF
+    pub duration: Option<f64>, // Duração do #EXTINF em segundos (None para -1 ou ausente)
+    pub http: HttpOptions,
+    pub raw_lines: Vec<String>, // Linhas originais antes da URL (#EXTINF, comentários, diretivas desconhecidas)
+}
+
+impl M3uEntry {
+    pub fn to_write(&self) -> EntryRef<'_> {
+        EntryRef {
+            title: &self.title,
+            url: &self.url,
+            logo: self.logo.as_deref(),
+            group: self.group.as_deref(),
+            duration: self.duration,
+            http: &self.http,
+            raw_lines: &self.raw_lines,
+        }
+    }
+}
+
+// Campos de uma entrada para gravar (lida do arquivo, colada ou editada)
+pub struct EntryRef<'a> {
+    pub title: &'a str,
+    pub url: &'a str,
+    pub logo: Option<&'a str>,
+    pub group: Option<&'a str>,
+    pub duration: Option<f64>,
+    pub http: &'a HttpOptions,
+    pub raw_lines: &'a [String], // Vazio para entradas que não vieram de um arquivo M3U
+}
+
+// Playlist inteira, com o que não vira entrada guardado para reescrever o arquivo como era
+pub struct Playlist {
+    pub header: Vec<String>,  // Linhas antes da primeira entrada (#EXTM3U, #PLAYLIST...)
+    pub entries: Vec<M3uEntry>,
+    pub trailer: Vec<String>, // Linhas depois da última URL
+    pub crlf: bool,           // O arquivo usa quebras de linha \r\n
+    pub final_newline: bool,  // A última linha termina com quebra de linha
+    pub latin1: bool,         // O arquivo não é UTF-8: lido como Latin-1 e gravado de volta assim (ver encode)
+}
+
+// ID do vídeo: o do YouTube quando a URL for do YouTube, senão o hash MD5 da URL
+pub fn video_id(url: &str) -> String {
+    static YOUTUBE_ID: OnceLock<Regex> = OnceLock::new();
+    let youtube_id_regex = YOUTUBE_ID.get_or_init(|| {
+        Regex::new(r"(?:youtu\.be/|youtube\.com/(?:embed/|v/|watch\?v=|watch\?.+&v=))([^?&/]+)").unwrap()
+    });
+    if let Some(captures) = youtube_id_regex.captures(url) {
+        captures.get(1).unwrap().as_str().to_string()
+    } else {
+        // Gerar um ID baseado no hash da URL se não for do YouTube
+        format!("{:x}", md5::compute(url.as_bytes()))
+    }
+}
+
+pub fn parse(reader: impl BufRead) -> Vec<M3uEntry> {
+    parse_playlist(reader).entries
+}
+
+pub fn parse_playlist(mut reader: impl BufRead) -> Playlist {
+    let logo_regex = Regex::new(r#"tvg-logo="([^"]*)""#).unwrap();
+    let group_regex = Regex::new(r#"group-title="([^"]*)""#).unwrap();
+
+    let mut entries = Vec::new();
+    let mut current_title = String::new();
+    let mut current_logo = None;
+    let mut current_group = None;
+    let mut current_duration = None;
+    let mut current_http = HttpOptions::default();
+
+    let mut header = None;
+    let mut pending = Vec::new(); // Linhas desde a última URL
+    let mut crlf = false;
+    let mut final_newline = true;
+
+    let mut lines = Vec::new();
+    let mut buffer = Vec::new();
+    while matches!(reader.read_until(b'\n', &mut buffer), Ok(n) if n > 0) {
+        lines.push(std::mem::take(&mut buffer));
+    }
+    // Listas antigas do Windows costumam estar em Latin-1. Cada byte vira a letra de mesmo código,
+    // então as linhas que não forem editadas voltam ao arquivo exatamente como eram.
+    let latin1 = lines.iter().any(|line| std::str::from_utf8(line).is_err());
+
+    for bytes in lines {
+        let mut line = if latin1 {
+            bytes.into_iter().map(char::from).collect()
+        } else {
+            String::from_utf8(bytes).unwrap_or_default()
+        };
+        final_newline = line.ends_with('\n');
+        if line.ends_with('\n') {
+            line.pop();
+            if line.ends_with('\r') {
+                line.pop();
+                crlf = true;
+            }
+        }
+
+        // O cabeçalho termina onde começa a primeira entrada
+        let is_url = !line.starts_with("#") && !line.trim().is_empty();
+        let starts_entry = is_url
+            || ["#EXTINF", "#EXTVLCOPT:", "#EXTHTTP:", "#EXTGRP:"]
+                .iter()
+                .any(|directive| line.starts_with(directive));
+        if header.is_none() && starts_entry {
+            header = Some(std::mem::take(&mut pending));
+        }
+        if !is_url {
+            pending.push(line.clone());
+        }
+
+        if line.starts_with("#EXTINF") {
+            // Extrair título da linha EXTINF - pegar tudo após a primeira vírgula que não está entre aspas
+            if let Some(pos) = title_separator(&line) {
+                current_title = line[pos + 1..].trim().to_string();
+            }
+            current_logo = logo_regex
+                .captures(&line)
+                .map(|c| c[1].to_string())
+                .filter(|logo| !logo.is_empty());
+            current_group = group_regex
+                .captures(&line)
+                .map(|c| c[1].trim().to_string())
+                .filter(|group| !group.is_empty());
+            current_duration = extinf_duration(&line);
+        } else if let Some(option) = line.strip_prefix("#EXTVLCOPT:") {
+            current_http.parse_vlcopt(option);
+        } else if let Some(json) = line.strip_prefix("#EXTHTTP:") {
+            current_http.parse_exthttp(json);
+        } else if !line.starts_with("#") && !line.trim().is_empty() {
+            // Esta é uma linha de URL
+            // Extrair ID do vídeo da URL
+            let id = video_id(&line);
+
+            // Usar o título extraído ou a URL como fallback
+            let title = if current_title.is_empty() {
+                fallback_title(&line)
+            } else {
+                std::mem::take(&mut current_title)
+            };
+
+            entries.push(M3uEntry {
+                title,
+                url: line,
+                id,
+                logo: current_logo.take(),
+                group: current_group.take(),
+                duration: current_duration.take(),
+                http: std::mem::take(&mut current_http),
+                raw_lines: std::mem::take(&mut pending),
+            });
+        }
+    }
+
+    let (header, trailer) = match header {
+        Some(header) => (header, pending),
+        None => (pending, Vec::new()),
+    };
+    Playlist { header, entries, trailer, crlf, final_newline, latin1 }
+}
+
+// Bytes para gravar o texto de write_file na codificação do arquivo lido. Em Latin-1, letras que
+// não existem nele (digitadas num título) viram "?".
+pub fn encode(text: String, latin1: bool) -> Vec<u8> {
+    if !latin1 {
+        return text.into_bytes();
+    }
+    text.chars().map(|c| u8::try_from(c).unwrap_or(b'?')).collect()
+}
+
+// Texto do arquivo: cabeçalho, entradas e o final, com as quebras de linha do arquivo lido
+pub fn write_file<'a>(
+    header: &[String],
+    entries: impl Iterator<Item = EntryRef<'a>>,
+    trailer: &[String],
+    crlf: bool,
+    final_newline: bool,
+) -> String {
+    let mut out = String::new();
+    for line in header {
+        out.push_str(line);
+        out.push('\n');
+    }
+    for entry in entries {
+        write_entry_preserving(&mut out, &entry);
+    }
+    for line in trailer {
+        out.push_str(line);
+        out.push('\n');
+    }
+    if !final_newline {
+        out.pop();
+    }
+    if crlf {
+        out = out.replace('\n', "\r\n");
+    }
+    out
+}
+
+// "#EXTINF:213 tvg-logo=...,Título" -> 213 segundos
+fn extinf_duration(line: &str) -> Option<f64> {
+    let rest = line.strip_prefix("#EXTINF:")?;
+    let end = rest.find([' ', ',']).unwrap_or(rest.len());
+    rest[..end].trim().parse::<f64>().ok().filter(|seconds| *seconds > 0.0)
+}
+
+// Título de uma entrada sem #EXTINF: o fim da URL
+pub fn fallback_title(url: &str) -> String {
+    url.split('/').next_back().unwrap_or(url).to_string()
+}
+
+// URLs soltas num texto qualquer (ex.: copiado de um chat), uma entrada por URL
+pub fn parse_urls(text: &str) -> Vec<M3uEntry> {
+    let url_regex = Regex::new(r#"[a-zA-Z][a-zA-Z0-9+.-]*://[^\s<>"]+"#).unwrap();
+    let lines: Vec<&str> = url_regex
+        .find_iter(text)
+        .map(|m| m.as_str().trim_end_matches(['.', ',', ';', ')', ']', '\'']))
+        .collect();
+    parse(lines.join("\n").as_bytes())
+}
+
+// Posição da vírgula que separa os atributos do título no #EXTINF.
+// Atributos como group-title="A, B" podem conter vírgulas entre aspas.
+fn title_separator(line: &str) -> Option<usize> {
+    let mut in_quotes = false;
+    for (i, c) in line.char_indices() {
+        match c {
+            '"' => in_quotes = !in_quotes,
+            ',' if !in_quotes => return Some(i),
+            _ => {}
+        }
+    }
+    line.find(',')
+}
+
+// Reescreve uma entrada lida do arquivo: as linhas originais ficam como estão, só o título do
+// #EXTINF muda se foi editado. Entradas sem linhas originais (coladas de JSON ou XSPF, por
+// exemplo) usam write_entry; URLs soltas continuam soltas.
+pub fn write_entry_preserving(out: &mut String, entry: &EntryRef) {
+    let EntryRef { title, url, raw_lines, .. } = *entry;
+    let bare = entry.logo.is_none()
+        && entry.group.is_none()
+        && entry.duration.is_none()
+        && *entry.http == HttpOptions::default()
+        && title == fallback_title(url);
+    if raw_lines.is_empty() && !bare {
+        write_entry(out, entry);
+        return;
+    }
+    let mut has_extinf = false;
+    for line in raw_lines {
+        if line.starts_with("#EXTINF") {
+            has_extinf = true;
+            out.push_str(&replace_title(line, title));
+        } else {
+            out.push_str(line);
+        }
+        out.push('\n');
+    }
+    // Título editado numa entrada que só tinha a URL
+    if !has_extinf && title != fallback_title(url) {
+        out.push_str(&format!("#EXTINF:-1,{}\n", title));
+    }
+    out.push_str(url);
+    out.push('\n');
+}
+
+// Troca o título de uma linha #EXTINF mantendo duração e atributos
+fn replace_title(line: &str, title: &str) -> String {
+    let Some(pos) = title_separator(line) else {
+        return format!("{},{}", line, title);
+    };
+    let rest = &line[pos + 1..];
+    if rest.trim() == title {
+        return line.to_string();
+    }
+    let spaces = &rest[..rest.len() - rest.trim_start().len()];
+    format!("{}{}{}", &line[..=pos], spaces, title)
+}
+
+// Escreve uma entrada no formato M3U: duração, logo e grupo no #EXTINF e as opções HTTP para o
+// player usar (as linhas originais não entram)
+pub fn write_entry(out: &mut String, entry: &EntryRef) {
+    let EntryRef { title, url, http, .. } = *entry;
+    out.push_str("#EXTINF:");
+    match entry.duration {
+        Some(seconds) => out.push_str(&seconds.to_string()),
+        None => out.push_str("-1"),
+    }
+    if let Some(logo) = entry.logo {
+        out.push_str(&format!(" tvg-logo=\"{}\"", logo));
+    }
+    if let Some(group) = entry.group {
+        out.push_str(&format!(" group-title=\"{}\"", group));
+    }
+    out.push_str(&format!(",{}\n", title));
+    if let Some(user_agent) = &http.user_agent {
+        out.push_str(&format!("#EXTVLCOPT:http-user-agent={}\n", user_agent));
+    }
+    if let Some(referrer) = &http.referrer {
+        out.push_str(&format!("#EXTVLCOPT:http-referrer={}\n", referrer));
+    }
+    for option in &http.vlc_options {
+        out.push_str(&format!("#EXTVLCOPT:{}\n", option));
+    }
+    if !http.headers.is_empty() {
+        let map: Map<String, Value> = http
+            .headers
+            .iter()
+            .map(|(k, v)| (k.clone(), Value::String(v.clone())))
+            .collect();
+        out.push_str(&format!("#EXTHTTP:{}\n", Value::Object(map)));
+    }
+    out.push_str(url);
+    out.push('\n');
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Lê e grava de novo, trocando os títulos pelos de `titles` quando houver
+    fn round_trip(text: &str, titles: &[(usize, &str)]) -> String {
+        let playlist = parse_playlist(text.as_bytes());
+        let mut entries: Vec<(String, &M3uEntry)> = playlist.entries.iter().map(|e| (e.title.clone(), e)).collect();
+        for &(i, title) in titles {
+            entries[i].0 = title.to_string();
+        }
+        let entries = entries.iter().map(|(title, e)| EntryRef { title, ..e.to_write() });
+        write_file(&playlist.header, entries, &playlist.trailer, playlist.crlf, playlist.final_newline)
+    }
+
+    const PLAYLIST: &str = "#EXTM3U x-tvg-url=\"https://epg/guide.xml\"\n\
+#PLAYLIST:Minha lista\n\
+\n\
+# comentário solto\n\
+#EXTINF:-1 tvg-logo=\"https://img/1.png\" group-title=\"Notícias, BR\",Canal 1\n\
+#EXTVLCOPT:http-user-agent=VLC/3.0\n\
+#EXTVLCOPT:http-referrer=https://site/\n\
+#EXTVLCOPT:network-caching=1000\n\
+#EXTHTTP:{\"cookie\":\"a=1\"}\n\
+#EXT-X-DESCONHECIDA:valor\n\
+https://tv/1.m3u8\n\
+#EXTINF:213,Artista - Música\n\
+https://youtu.be/dQw4w9WgXcQ\n\
+https://sem-extinf/video.mp4\n\
+# fim da lista\n";
+
+    #[test]
+    fn campos_lidos() {
+        let playlist = parse_playlist(PLAYLIST.as_bytes());
+        assert_eq!(playlist.header, ["#EXTM3U x-tvg-url=\"https://epg/guide.xml\"", "#PLAYLIST:Minha lista", "", "# comentário solto"]);
+        assert_eq!(playlist.trailer, ["# fim da lista"]);
+        assert!(!playlist.crlf);
+        assert!(playlist.final_newline);
+
+        let [canal, musica, solto] = &playlist.entries[..] else {
+            panic!("esperava 3 entradas");
+        };
+        assert_eq!(canal.title, "Canal 1");
+        assert_eq!(canal.logo.as_deref(), Some("https://img/1.png"));
+        assert_eq!(canal.group.as_deref(), Some("Notícias, BR"));
+        assert_eq!(canal.duration, None);
+        assert_eq!(canal.http.user_agent.as_deref(), Some("VLC/3.0"));
+        assert_eq!(canal.http.referrer.as_deref(), Some("https://site/"));
+        assert_eq!(canal.http.vlc_options, ["network-caching=1000"]);
+        assert_eq!(canal.http.headers, [("cookie".to_string(), "a=1".to_string())]);
+        assert_eq!(canal.raw_lines.len(), 6);
+
+        assert_eq!(musica.title, "Artista - Música");
+        assert_eq!(musica.duration, Some(213.0));
+        assert_eq!(musica.id, "dQw4w9WgXcQ");
+
+        assert_eq!(solto.title, "video.mp4");
+        assert!(solto.raw_lines.is_empty());
+    }
+
+    #[test]
+    fn grava_igual_ao_que_leu() {
+        assert_eq!(round_trip(PLAYLIST, &[]), PLAYLIST);
+    }
+
+    #[test]
+    fn mantem_crlf() {
+        let crlf = PLAYLIST.replace('\n', "\r\n");
+        let playlist = parse_playlist(crlf.as_bytes());
+        assert!(playlist.crlf);
+        assert_eq!(playlist.entries[0].title, "Canal 1"); // Sem \r no título
+        assert_eq!(playlist.entries[0].url, "https://tv/1.m3u8");
+        assert_eq!(round_trip(&crlf, &[]), crlf);
+    }
+
+    #[test]
+    fn sem_quebra_de_linha_no_fim() {
+        let text = "#EXTM3U\n#EXTINF:-1,Um\nhttps://a/1";
+        let playlist = parse_playlist(text.as_bytes());
+        assert!(!playlist.final_newline);
+        assert_eq!(playlist.entries[0].url, "https://a/1");
+        assert_eq!(round_trip(text, &[]), text);
+        assert_eq!(round_trip("#EXTM3U\r\nhttps://a/1", &[]), "#EXTM3U\r\nhttps://a/1");
+    }
+
+    #[test]
+    fn latin1_volta_igual() {
+        let mut bytes = b"#EXTM3U\n#EXTINF:-1,Can\xe7\xe3o\n".to_vec();
+        bytes.extend_from_slice("https://a/1\n#EXTINF:-1,Ação\nhttps://a/2\n".as_bytes()); // Mistura com UTF-8
+        bytes.extend_from_slice(b"#EXTINF:-1,Depois\nhttps://a/3\n");
+        let playlist = parse_playlist(bytes.as_slice());
+        assert!(playlist.latin1);
+        assert_eq!(playlist.entries.len(), 3); // Nada se perde depois da linha que não é UTF-8
+        assert_eq!(playlist.entries[0].title, "Canção");
+
+        let entries = playlist.entries.iter().map(M3uEntry::to_write);
+        let text = write_file(&playlist.header, entries, &playlist.trailer, playlist.crlf, playlist.final_newline);
+        assert_eq!(encode(text, playlist.latin1), bytes);
+        assert_eq!(encode("Ação 😀".to_string(), true), b"A\xe7\xe3o ?");
+        assert!(!parse_playlist(PLAYLIST.as_bytes()).latin1);
+    }
+
+    #[test]
+    fn titulo_editado() {
+        let edited = round_trip(PLAYLIST, &[(0, "Canal Um"), (2, "Vídeo solto")]);
+        // Só o título muda: duração, atributos e demais linhas ficam como estavam
+        let expected = PLAYLIST
+            .replace("group-title=\"Notícias, BR\",Canal 1", "group-title=\"Notícias, BR\",Canal Um")
+            .replace("https://sem-extinf/video.mp4\n", "#EXTINF:-1,Vídeo solto\nhttps://sem-extinf/video.mp4\n");
+        assert_eq!(edited, expected);
+
+        // Espaço depois da vírgula é mantido
+        assert_eq!(replace_title("#EXTINF:-1, Antigo", "Novo"), "#EXTINF:-1, Novo");
+    }
+
+    #[test]
+    fn entrada_nova_leva_as_opcoes_http() {
+        let http = HttpOptions {
+            user_agent: Some("VLC/3.0".to_string()),
+            headers: vec![("Cookie".to_string(), "a=1".to_string())],
+            ..Default::default()
+        };
+        let new = EntryRef {
+            title: "Novo",
+            url: "https://a/2",
+            logo: None,
+            group: None,
+            duration: None,
+            http: &http,
+            raw_lines: &[],
+        };
+        let mut out = String::new();
+        write_entry(&mut out, &new);
+        let entry = &parse(out.as_bytes())[0];
+        assert_eq!(entry.title, "Novo");
+        assert_eq!(entry.http, http);
+    }
+
+    #[test]
+    fn copiar_e_colar_mantem_grupo_logo_e_duracao() {
+        // Como os fragmentos do Ctrl+C e as playlists temporárias: sem as linhas originais
+        let playlist = parse_playlist(PLAYLIST.as_bytes());
+        let mut out = String::new();
+        for entry in &playlist.entries {
+            write_entry(&mut out, &entry.to_write());
+        }
+        let copied = parse(out.as_bytes());
+        for (copy, original) in copied.iter().zip(&playlist.entries) {
+            assert_eq!(copy.title, original.title);
+            assert_eq!(copy.logo, original.logo);
+            assert_eq!(copy.group, original.group);
+            assert_eq!(copy.duration, original.duration);
+            assert_eq!(copy.http, original.http);
+        }
+        assert!(out.contains("#EXTINF:-1 tvg-logo=\"https://img/1.png\" group-title=\"Notícias, BR\",Canal 1\n"));
+        assert!(out.contains("#EXTINF:213,Artista - Música\n"));
+    }
+
+    #[test]
+    fn entrada_colada_sem_linhas_originais() {
+        // Vinda de JSON ou XSPF: grupo, logo e duração entram no arquivo salvo
+        let http = HttpOptions::default();
+        let pasted = EntryRef {
+            title: "Colado",
+            url: "https://a/3",
+            logo: Some("https://img/3.png"),
+            group: Some("Filmes"),
+            duration: Some(90.0),
+            http: &http,
+            raw_lines: &[],
+        };
+        let text = write_file(&["#EXTM3U".to_string()], [pasted].into_iter(), &[], false, true);
+        assert_eq!(text, "#EXTM3U\n#EXTINF:90 tvg-logo=\"https://img/3.png\" group-title=\"Filmes\",Colado\nhttps://a/3\n");
+        // URL solta continua solta
+        let bare = EntryRef { title: "3", logo: None, group: None, duration: None, url: "https://a/3", http: &http, raw_lines: &[] };
+        assert_eq!(write_file(&[], [bare].into_iter(), &[], false, true), "https://a/3\n");
+    }
+}
@@ -0,0 +1,317 @@
+// Playlists do usuário, tags dos vídeos e pesquisas salvas, guardadas em m3u.json ao lado da
+// playlist aberta.
+// Tudo é indexado pelo ID do vídeo (VideoEntry::id), então o mesmo m3u.json serve para
+// várias playlists da mesma pasta.
+use crate::util::write_atomic;
+use crate::view::SavedSearch;
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use std::collections::{BTreeMap, HashSet};
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+pub const FILE_NAME: &str = "m3u.json";
+
+// Tag numérica usada como nota no sorteio "Pela nota"
+pub const RATING_TAG: &str = "nota";
+
+#[derive(Serialize, Deserialize, Clone, Default, PartialEq, Debug)]
+#[serde(from = "StoredPlaylist")]
+pub struct UserPlaylist {
+    videos: Vec<String>, // IDs na ordem em que foram adicionados
+    #[serde(skip)]
+    members: HashSet<String>, // Os mesmos IDs, para consultar sem percorrer a lista
+}
+
+// UserPlaylist como está no arquivo
+#[derive(Deserialize)]
+struct StoredPlaylist {
+    #[serde(default)]
+    videos: Vec<String>,
+}
+
+impl From<StoredPlaylist> for UserPlaylist {
+    fn from(stored: StoredPlaylist) -> Self {
+        let mut playlist = Self::default();
+        for id in stored.videos {
+            playlist.push(id);
+        }
+        playlist
+    }
+}
+
+impl UserPlaylist {
+    pub fn len(&self) -> usize {
+        self.videos.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.videos.is_empty()
+    }
+
+    pub fn contains(&self, id: &str) -> bool {
+        self.members.contains(id)
+    }
+
+    // Adiciona no fim, se ainda não estiver
+    fn push(&mut self, id: String) {
+        if self.members.insert(id.clone()) {
+            self.videos.push(id);
+        }
+    }
+
+    // Troca um ID por outro na mesma posição (ou acrescenta o novo, com `keep`)
+    fn rename(&mut self, old: &str, new: &str, keep: bool) {
+        if !self.contains(old) || self.contains(new) {
+            return;
+        }
+        if keep {
+            self.push(new.to_string());
+            return;
+        }
+        if let Some(slot) = self.videos.iter_mut().find(|v| *v == old) {
+            *slot = new.to_string();
+        }
+        self.members.remove(old);
+        self.members.insert(new.to_string());
+    }
+
+    fn remove(&mut self, ids: &HashSet<&str>) {
+        if ids.iter().any(|id| self.members.contains(*id)) {
+            self.videos.retain(|v| !ids.contains(v.as_str()));
+            self.members.retain(|v| !ids.contains(v.as_str()));
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Default, PartialEq, Debug)]
+pub struct Metadata {
+    #[serde(default)]
+    pub playlist: BTreeMap<String, UserPlaylist>,
+    #[serde(default)]
+    pub tag: BTreeMap<String, BTreeMap<String, Option<String>>>, // tag -> id -> valor (opcional)
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub smart_playlist: BTreeMap<String, SavedSearch>, // Pesquisas salvas, pelo nome
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub player: BTreeMap<String, String>, // id -> perfil escolhido em "Abrir com"
+    #[serde(flatten)]
+    pub extra: Map<String, Value>, // Chaves desconhecidas, mantidas ao salvar
+}
+
+// m3u.json da pasta da playlist
+pub fn path_for(playlist: &Path) -> PathBuf {
+    playlist.with_file_name(FILE_NAME)
+}
+
+impl Metadata {
+    // Arquivo inexistente vale como vazio; um arquivo inválido é erro (para não ser sobrescrito)
+    pub fn load(path: &Path) -> Result<Self, String> {
+        match fs::read_to_string(path) {
+            Ok(text) => serde_json::from_str(&text).map_err(|e| format!("{} inválido: {}", path.display(), e)),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(Self::default()),
+            Err(e) => Err(format!("Erro ao ler {}: {}", path.display(), e)),
+        }
+    }
+
+    pub fn save(&self, path: &Path) -> io::Result<()> {
+        let json = serde_json::to_string_pretty(self).map_err(io::Error::other)?;
+        write_atomic(path, json.as_bytes())
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.playlist.is_empty() && self.tag.is_empty() && self.smart_playlist.is_empty() && self.player.is_empty()
+    }
+
+    // Quantos registros guarda (vídeos em playlists, tags, players e pesquisas), para o limite do histórico
+    pub fn len(&self) -> usize {
+        self.playlist.values().map(UserPlaylist::len).sum::<usize>()
+            + self.tag.values().map(BTreeMap::len).sum::<usize>()
+            + self.player.len()
+            + self.smart_playlist.len()
+    }
+
+    // Junta outro m3u.json neste (usado no "Salvar como" para outra pasta)
+    pub fn merge(&mut self, other: Metadata) {
+        for (name, playlist) in other.playlist {
+            let target = self.playlist.entry(name).or_default();
+            for id in playlist.videos {
+                target.push(id);
+            }
+        }
+        for (name, values) in other.tag {
+            self.tag.entry(name).or_default().extend(values);
+        }
+        for (name, search) in other.smart_playlist {
+            self.smart_playlist.entry(name).or_insert(search);
+        }
+        for (id, player) in other.player {
+            self.player.entry(id).or_insert(player);
+        }
+        for (key, value) in other.extra {
+            self.extra.entry(key).or_insert(value);
+        }
+    }
+
+    pub fn playlist_names(&self) -> impl Iterator<Item = &str> {
+        self.playlist.keys().map(String::as_str)
+    }
+
+    pub fn tag_names(&self) -> impl Iterator<Item = &str> {
+        self.tag.keys().map(String::as_str)
+    }
+
+    // Valores já usados numa tag, sem repetir
+    pub fn tag_values(&self, name: &str) -> Vec<&str> {
+        let mut values: Vec<&str> = self
+            .tag
+            .get(name)
+            .into_iter()
+            .flat_map(|ids| ids.values().filter_map(|v| v.as_deref()))
+            .collect();
+        values.sort_unstable();
+        values.dedup();
+        values
+    }
+
+    pub fn playlists_of(&self, id: &str) -> Vec<&str> {
+        self.playlist
+            .iter()
+            .filter(|(_, playlist)| playlist.contains(id))
+            .map(|(name, _)| name.as_str())
+            .collect()
+    }
+
+    pub fn tags_of(&self, id: &str) -> Vec<(&str, Option<&str>)> {
+        self.tag
+            .iter()
+            .filter_map(|(name, ids)| ids.get(id).map(|value| (name.as_str(), value.as_deref())))
+            .collect()
+    }
+
+    pub fn tag_value(&self, name: &str, id: &str) -> Option<&str> {
+        self.tag.get(name)?.get(id)?.as_deref()
+    }
+
+    pub fn in_playlist(&self, name: &str, id: &str) -> bool {
+        self.playlist.get(name).is_some_and(|playlist| playlist.contains(id))
+    }
+
+    pub fn add_to_playlist(&mut self, name: &str, id: &str) {
+        let playlist = self.playlist.entry(name.to_string()).or_default();
+        if !playlist.contains(id) {
+            playlist.push(id.to_string());
+        }
+    }
+
+    // Tira vários vídeos de uma vez (uma passada pela lista); playlists que ficam vazias deixam de existir
+    pub fn remove_from_playlist(&mut self, name: &str, ids: &[String]) {
+        if let Some(playlist) = self.playlist.get_mut(name) {
+            playlist.remove(&ids.iter().map(String::as_str).collect());
+            if playlist.is_empty() {
+                self.playlist.remove(name);
+            }
+        }
+    }
+
+    pub fn set_tag(&mut self, name: &str, id: &str, value: Option<String>) {
+        let value = value.filter(|v| !v.is_empty());
+        self.tag.entry(name.to_string()).or_default().insert(id.to_string(), value);
+    }
+
+    pub fn remove_tag(&mut self, name: &str, id: &str) {
+        if let Some(ids) = self.tag.get_mut(name) {
+            ids.remove(id);
+            if ids.is_empty() {
+                self.tag.remove(name);
+            }
+        }
+    }
+
+    // Perfil de player lembrado para o vídeo (None: o padrão)
+    pub fn player_of(&self, id: &str) -> Option<&str> {
+        self.player.get(id).map(String::as_str)
+    }
+
+    pub fn set_player(&mut self, id: &str, player: Option<&str>) {
+        match player {
+            Some(name) => self.player.insert(id.to_string(), name.to_string()),
+            None => self.player.remove(id),
+        };
+    }
+
+    // O vídeo mudou de ID (URL editada): playlists, tags e player passam para o novo, sem apagar
+    // o que o novo já tinha. Com `keep` o ID antigo também continua (outra entrada ainda o usa).
+    pub fn move_id(&mut self, old: &str, new: &str, keep: bool) {
+        for playlist in self.playlist.values_mut() {
+            playlist.rename(old, new, keep);
+        }
+        for ids in self.tag.values_mut() {
+            let value = if keep { ids.get(old).cloned() } else { ids.remove(old) };
+            if let Some(value) = value {
+                ids.entry(new.to_string()).or_insert(value);
+            }
+        }
+        let player = if keep { self.player.get(old).cloned() } else { self.player.remove(old) };
+        if let Some(player) = player {
+            self.player.entry(new.to_string()).or_insert(player);
+        }
+    }
+
+    // Valor numérico da tag "nota" ("8", "7,5"); valores como "top" não contam
+    pub fn rating(&self, id: &str) -> Option<f64> {
+        self.tag_value(RATING_TAG, id)?.trim().replace(',', ".").parse().ok()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn playlists_do_arquivo() {
+        let json = r#"{"playlist": {"Favoritos": {"videos": ["a", "b", "a"]}}, "outra_chave": 1}"#;
+        let mut metadata: Metadata = serde_json::from_str(json).unwrap();
+        assert!(metadata.in_playlist("Favoritos", "b"));
+        assert_eq!(metadata.playlist["Favoritos"].len(), 2); // "a" repetido conta uma vez
+
+        metadata.add_to_playlist("Favoritos", "c");
+        metadata.add_to_playlist("Favoritos", "a");
+        assert_eq!(metadata.playlists_of("c"), ["Favoritos"]);
+
+        let saved: Value = serde_json::to_value(&metadata).unwrap();
+        assert_eq!(saved["playlist"]["Favoritos"]["videos"], serde_json::json!(["a", "b", "c"]));
+        assert_eq!(saved["outra_chave"], 1);
+
+        metadata.remove_from_playlist("Favoritos", &["a".to_string(), "c".to_string()]);
+        assert!(!metadata.in_playlist("Favoritos", "a"));
+        assert!(metadata.in_playlist("Favoritos", "b"));
+        metadata.remove_from_playlist("Favoritos", &["b".to_string()]);
+        assert!(metadata.playlist.is_empty());
+    }
+
+    #[test]
+    fn url_editada_leva_tags_e_playlists() {
+        let mut metadata = Metadata::default();
+        for id in ["x", "velho", "y"] {
+            metadata.add_to_playlist("Favoritos", id);
+        }
+        metadata.set_tag("nota", "velho", Some("8".to_string()));
+        metadata.set_player("velho", Some("mpv"));
+
+        let mut shared = metadata.clone();
+        metadata.move_id("velho", "novo", false);
+        assert_eq!(metadata.playlist["Favoritos"].videos, ["x", "novo", "y"]); // Mesma posição
+        assert!(!metadata.in_playlist("Favoritos", "velho"));
+        assert_eq!(metadata.tags_of("novo"), [("nota", Some("8"))]);
+        assert!(metadata.tags_of("velho").is_empty());
+        assert_eq!(metadata.player_of("novo"), Some("mpv"));
+        assert_eq!(metadata.player_of("velho"), None);
+
+        // Outra entrada ainda usa o ID antigo
+        shared.move_id("velho", "novo", true);
+        assert!(shared.in_playlist("Favoritos", "velho") && shared.in_playlist("Favoritos", "novo"));
+        assert_eq!(shared.tag_value("nota", "velho"), Some("8"));
+        assert_eq!(shared.tag_value("nota", "novo"), Some("8"));
+    }
+}
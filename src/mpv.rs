@@ -0,0 +1,363 @@
+// Integração com o mpv pelo IPC JSON (--input-ipc-server)
+//
+// Uma tarefa em segundo plano mantém a conexão: envia os comandos vindos da GUI e
+// atualiza MpvState com as propriedades observadas (arquivo atual, pausa, posição...).
+use crate::m3u::HttpOptions;
+use serde_json::{json, Value};
+use std::io;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tokio::io::{AsyncBufReadExt, AsyncRead, AsyncWrite, AsyncWriteExt, BufReader};
+use tokio::sync::mpsc::{self, UnboundedReceiver, UnboundedSender};
+use tokio::task::JoinHandle;
+
+// Propriedades observadas; o índice é usado como ID da observação
+const OBSERVED: [&str; 5] = ["path", "pause", "time-pos", "duration", "media-title"];
+
+#[derive(Default, Clone)]
+pub struct MpvState {
+    pub connected: bool,
+    pub path: Option<String>,  // URL/arquivo tocando agora
+    pub paused: bool,
+    pub time_pos: Option<f64>, // Segundos
+    pub duration: Option<f64>,
+    pub title: Option<String>,
+}
+
+// Caminho padrão do socket/pipe usado quando o app abre o mpv
+pub fn default_socket() -> String {
+    #[cfg(windows)]
+    {
+        r"\\.\pipe\m3u8-gui-mpv".to_string()
+    }
+    #[cfg(not(windows))]
+    {
+        std::env::temp_dir().join("m3u8-gui-mpv.sock").display().to_string()
+    }
+}
+
+// Verifica rapidamente se há um mpv ouvindo no socket
+pub fn socket_available(socket: &str) -> bool {
+    #[cfg(unix)]
+    {
+        std::os::unix::net::UnixStream::connect(socket).is_ok()
+    }
+    #[cfg(windows)]
+    {
+        std::fs::OpenOptions::new().read(true).write(true).open(socket).is_ok()
+    }
+}
+
+#[derive(Default)]
+pub struct MpvController {
+    state: Arc<Mutex<MpvState>>,
+    commands: Option<UnboundedSender<Value>>,
+    task: Option<JoinHandle<()>>,
+}
+
+impl MpvController {
+    pub fn state(&self) -> MpvState {
+        self.state.lock().unwrap().clone()
+    }
+
+    pub fn is_connected(&self) -> bool {
+        self.state.lock().unwrap().connected
+    }
+
+    // Conecta ao socket, tentando de novo até `retry_for` (o mpv recém-aberto demora a criar o socket).
+    // `initial` são comandos enviados assim que a conexão abrir.
+    pub fn connect(&mut self, socket: String, retry_for: Duration, initial: Vec<Value>, ctx: egui::Context) {
+        self.disconnect();
+
+        let (tx, rx) = mpsc::unbounded_channel();
+        for command in initial {
+            let _ = tx.send(command);
+        }
+        let state = Arc::clone(&self.state);
+        self.commands = Some(tx);
+        self.task = Some(tokio::spawn(async move {
+            let deadline = Instant::now() + retry_for;
+            loop {
+                match open(&socket).await {
+                    Ok(stream) => {
+                        run(stream, rx, &state, &ctx).await;
+                        break;
+                    }
+                    Err(_) if Instant::now() < deadline => {
+                        tokio::time::sleep(Duration::from_millis(200)).await;
+                    }
+                    Err(_) => break,
+                }
+            }
+            *state.lock().unwrap() = MpvState::default();
+            ctx.request_repaint();
+        }));
+    }
+
+    pub fn disconnect(&mut self) {
+        if let Some(task) = self.task.take() {
+            task.abort();
+        }
+        self.commands = None;
+        *self.state.lock().unwrap() = MpvState::default();
+    }
+
+    // Envia um comando, ex.: ["loadfile", url, "append-play"]. Retorna false se não houver conexão.
+    pub fn command(&self, args: Value) -> bool {
+        self.is_connected() && self.commands.as_ref().is_some_and(|tx| tx.send(args).is_ok())
+    }
+
+    pub fn toggle_pause(&self) -> bool {
+        self.command(json!(["cycle", "pause"]))
+    }
+
+    pub fn next(&self) -> bool {
+        self.command(json!(["playlist-next"]))
+    }
+
+    pub fn prev(&self) -> bool {
+        self.command(json!(["playlist-prev"]))
+    }
+
+    pub fn seek_relative(&self, seconds: f64) -> bool {
+        self.command(json!(["seek", seconds, "relative"]))
+    }
+
+    pub fn seek_absolute(&self, seconds: f64) -> bool {
+        self.command(json!(["seek", seconds, "absolute"]))
+    }
+
+    // Adiciona arquivos ao fim da fila do mpv, começando a tocar se ele estiver parado
+    pub fn enqueue(&self, files: &[(&str, &HttpOptions)]) -> bool {
+        files
+            .iter()
+            .all(|(url, http)| self.command(loadfile(url, "append-play", http)))
+    }
+
+    // Insere arquivos logo depois do vídeo atual, na ordem dada (insert-next existe desde o mpv 0.38)
+    pub fn play_next(&self, files: &[(&str, &HttpOptions)]) -> bool {
+        files
+            .iter()
+            .rev()
+            .all(|(url, http)| self.command(loadfile(url, "insert-next", http)))
+    }
+}
+
+impl Drop for MpvController {
+    fn drop(&mut self) {
+        if let Some(task) = self.task.take() {
+            task.abort();
+        }
+    }
+}
+
+// Comando loadfile com as opções HTTP da entrada valendo só para aquele arquivo (o índice -1 é
+// obrigatório antes das opções desde o mpv 0.38)
+pub fn loadfile(url: &str, mode: &str, http: &HttpOptions) -> Value {
+    let options = file_options(http);
+    if options.is_empty() {
+        json!(["loadfile", url, mode])
+    } else {
+        json!(["loadfile", url, mode, -1, options])
+    }
+}
+
+// "user-agent=...,referrer=...,http-header-fields=..." com cada valor no formato %tamanho%valor,
+// para vírgulas e sinais de igual dos valores não quebrarem a lista
+fn file_options(http: &HttpOptions) -> String {
+    let headers = http
+        .headers
+        .iter()
+        .map(|(name, value)| format!("{}: {}", name, value))
+        .collect::<Vec<_>>()
+        .join(",");
+    let options = [
+        ("user-agent", http.user_agent.as_deref().unwrap_or("")),
+        ("referrer", http.referrer.as_deref().unwrap_or("")),
+        ("http-header-fields", headers.as_str()),
+    ];
+    options
+        .iter()
+        .filter(|(_, value)| !value.is_empty())
+        .map(|(name, value)| format!("{}=%{}%{}", name, value.len(), value))
+        .collect::<Vec<_>>()
+        .join(",")
+}
+
+#[cfg(unix)]
+async fn open(socket: &str) -> io::Result<tokio::net::UnixStream> {
+    tokio::net::UnixStream::connect(socket).await
+}
+
+#[cfg(windows)]
+async fn open(socket: &str) -> io::Result<tokio::net::windows::named_pipe::NamedPipeClient> {
+    tokio::net::windows::named_pipe::ClientOptions::new().open(socket)
+}
+
+// Mantém a conexão até o mpv fechar
+async fn run<S>(stream: S, mut commands: UnboundedReceiver<Value>, state: &Mutex<MpvState>, ctx: &egui::Context)
+where
+    S: AsyncRead + AsyncWrite,
+{
+    let (reader, mut writer) = tokio::io::split(stream);
+    let mut lines = BufReader::new(reader).lines();
+
+    state.lock().unwrap().connected = true;
+    ctx.request_repaint();
+
+    for (id, name) in OBSERVED.iter().enumerate() {
+        let line = json!({ "command": ["observe_property", id + 1, name] }).to_string() + "\n";
+        if writer.write_all(line.as_bytes()).await.is_err() {
+            return;
+        }
+    }
+
+    loop {
+        tokio::select! {
+            command = commands.recv() => {
+                let Some(command) = command else { return };
+                let line = json!({ "command": command }).to_string() + "\n";
+                if writer.write_all(line.as_bytes()).await.is_err() {
+                    return;
+                }
+            }
+            line = lines.next_line() => {
+                let Ok(Some(line)) = line else { return };
+                if let Ok(message) = serde_json::from_str::<Value>(&line) {
+                    if apply_event(&mut state.lock().unwrap(), &message) {
+                        ctx.request_repaint();
+                    }
+                }
+            }
+        }
+    }
+}
+
+// Atualiza o estado com um evento do mpv; retorna true se algo mudou
+fn apply_event(state: &mut MpvState, message: &Value) -> bool {
+    match message["event"].as_str() {
+        Some("property-change") => {
+            let data = &message["data"];
+            match message["name"].as_str() {
+                Some("path") => state.path = data.as_str().map(str::to_string),
+                Some("pause") => state.paused = data.as_bool().unwrap_or(false),
+                Some("time-pos") => state.time_pos = data.as_f64(),
+                Some("duration") => state.duration = data.as_f64(),
+                Some("media-title") => state.title = data.as_str().map(str::to_string),
+                _ => return false,
+            }
+            true
+        }
+        Some("idle") | Some("end-file") => {
+            state.time_pos = None;
+            true
+        }
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn change(name: &str, data: Value) -> Value {
+        json!({ "event": "property-change", "name": name, "data": data })
+    }
+
+    #[test]
+    fn eventos_atualizam_o_estado() {
+        let mut state = MpvState::default();
+        assert!(apply_event(&mut state, &change("path", json!("https://a/1.mp4"))));
+        assert!(apply_event(&mut state, &change("pause", json!(true))));
+        assert!(apply_event(&mut state, &change("time-pos", json!(12.5))));
+        assert!(apply_event(&mut state, &change("duration", json!(60.0))));
+        assert!(apply_event(&mut state, &change("media-title", json!("Ep 1"))));
+        assert_eq!(state.path.as_deref(), Some("https://a/1.mp4"));
+        assert!(state.paused);
+        assert_eq!(state.time_pos, Some(12.5));
+        assert_eq!(state.duration, Some(60.0));
+        assert_eq!(state.title.as_deref(), Some("Ep 1"));
+
+        // Propriedade sem valor (nada tocando) e eventos desconhecidos
+        assert!(apply_event(&mut state, &change("path", Value::Null)));
+        assert_eq!(state.path, None);
+        assert!(!apply_event(&mut state, &change("volume", json!(50))));
+        assert!(!apply_event(&mut state, &json!({ "request_id": 0, "error": "success" })));
+
+        assert!(apply_event(&mut state, &json!({ "event": "end-file" })));
+        assert_eq!(state.time_pos, None);
+    }
+
+    #[test]
+    fn loadfile_leva_as_opcoes_http() {
+        let http = HttpOptions::default();
+        assert_eq!(loadfile("u", "append-play", &http), json!(["loadfile", "u", "append-play"]));
+
+        let http = HttpOptions {
+            user_agent: Some("VLC/3.0".to_string()),
+            referrer: Some("https://site/".to_string()),
+            headers: vec![("Cookie".to_string(), "a=1, b=2".to_string())],
+            ..Default::default()
+        };
+        assert_eq!(
+            loadfile("u", "insert-next", &http),
+            json!([
+                "loadfile",
+                "u",
+                "insert-next",
+                -1,
+                "user-agent=%7%VLC/3.0,referrer=%13%https://site/,http-header-fields=%16%Cookie: a=1, b=2"
+            ])
+        );
+    }
+
+    // mpv falso num socket Unix: a conexão observa as propriedades, repassa comandos e aplica eventos
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn conversa_com_um_mpv_falso() {
+        use tokio::net::UnixListener;
+
+        let dir = tempfile::tempdir().unwrap();
+        let socket = dir.path().join("mpv.sock");
+        let listener = UnixListener::bind(&socket).unwrap();
+
+        let mut mpv = MpvController::default();
+        mpv.connect(socket.display().to_string(), Duration::from_secs(1), Vec::new(), egui::Context::default());
+
+        let (stream, _) = listener.accept().await.unwrap();
+        let (reader, mut writer) = tokio::io::split(stream);
+        let mut lines = BufReader::new(reader).lines();
+        for (id, name) in OBSERVED.iter().enumerate() {
+            let line: Value = serde_json::from_str(&lines.next_line().await.unwrap().unwrap()).unwrap();
+            assert_eq!(line["command"], json!(["observe_property", id + 1, name]));
+        }
+
+        let http = HttpOptions {
+            referrer: Some("https://site/".to_string()),
+            ..Default::default()
+        };
+        assert!(mpv.enqueue(&[("https://a/1.mp4", &http)]));
+        let line: Value = serde_json::from_str(&lines.next_line().await.unwrap().unwrap()).unwrap();
+        assert_eq!(
+            line["command"],
+            json!(["loadfile", "https://a/1.mp4", "append-play", -1, "referrer=%13%https://site/"])
+        );
+
+        let event = change("media-title", json!("Ep 1")).to_string() + "\n";
+        writer.write_all(event.as_bytes()).await.unwrap();
+        let deadline = Instant::now() + Duration::from_secs(2);
+        while mpv.state().title.is_none() && Instant::now() < deadline {
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+        assert_eq!(mpv.state().title.as_deref(), Some("Ep 1"));
+
+        // mpv fechou: a conexão cai
+        drop(writer);
+        drop(lines);
+        let deadline = Instant::now() + Duration::from_secs(2);
+        while mpv.is_connected() && Instant::now() < deadline {
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+        assert!(!mpv.is_connected());
+    }
+}
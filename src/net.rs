@@ -0,0 +1,79 @@
+// Cliente HTTP compartilhado, montado a partir das configurações de rede
+use crate::config::{NetworkConfig, ProxyMode};
+use reqwest::header::{HeaderMap, HeaderName, HeaderValue};
+use reqwest::{Certificate, Client, Proxy};
+use std::fs;
+use std::time::Duration;
+
+// Monta o cliente usado por todos os downloads. Retorna None no modo offline.
+pub fn build_client(config: &NetworkConfig) -> Result<Option<Client>, String> {
+    if config.offline {
+        return Ok(None);
+    }
+
+    let mut builder = Client::builder()
+        .connect_timeout(Duration::from_secs(config.connect_timeout_secs.max(1)))
+        .timeout(Duration::from_secs(config.timeout_secs.max(1)));
+
+    builder = match config.proxy_mode {
+        ProxyMode::Sistema => builder, // reqwest já lê HTTP_PROXY/HTTPS_PROXY/ALL_PROXY
+        ProxyMode::Nenhum => builder.no_proxy(),
+        ProxyMode::Manual => {
+            // Aceita http://, https://, socks5:// e socks5h://, com usuário:senha@ opcional
+            let proxy = Proxy::all(config.proxy_url.trim())
+                .map_err(|e| format!("Proxy inválido: {}", e))?;
+            builder.proxy(proxy)
+        }
+    };
+
+    if !config.user_agent.trim().is_empty() {
+        builder = builder.user_agent(config.user_agent.trim());
+    }
+
+    let mut headers = HeaderMap::new();
+    for (name, value) in &config.headers {
+        if name.trim().is_empty() {
+            continue;
+        }
+        let name = HeaderName::from_bytes(name.trim().as_bytes())
+            .map_err(|_| format!("Nome de cabeçalho inválido: {}", name))?;
+        let value = HeaderValue::from_str(value.trim())
+            .map_err(|_| format!("Valor inválido para o cabeçalho {}", name))?;
+        headers.append(name, value);
+    }
+    builder = builder.default_headers(headers);
+
+    if config.accept_invalid_certs {
+        builder = builder.danger_accept_invalid_certs(true);
+    }
+
+    // Certificado raiz extra, para proxies corporativos que inspecionam HTTPS
+    if !config.ca_certificate.trim().is_empty() {
+        let pem = fs::read(config.ca_certificate.trim())
+            .map_err(|e| format!("Não foi possível ler o certificado: {}", e))?;
+        let certificate = Certificate::from_pem(&pem)
+            .map_err(|e| format!("Certificado inválido: {}", e))?;
+        builder = builder.add_root_certificate(certificate);
+    }
+
+    builder
+        .build()
+        .map(Some)
+        .map_err(|e| format!("Não foi possível criar o cliente HTTP: {}", e))
+}
+
+// Baixa o texto de uma playlist remota
+pub async fn fetch_text(client: Client, url: String) -> Result<String, String> {
+    let response = client
+        .get(&url)
+        .send()
+        .await
+        .map_err(|e| format!("Erro de rede: {}", e))?;
+    if !response.status().is_success() {
+        return Err(format!("O servidor respondeu {}", response.status()));
+    }
+    response
+        .text()
+        .await
+        .map_err(|e| format!("Erro ao ler a resposta: {}", e))
+}
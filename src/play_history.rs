@@ -0,0 +1,162 @@
+// Histórico de reproduções, guardado em play_history.json (ao lado do config.json). Cada vídeo
+// mandado para o player fica registrado com o ID, quando e de qual playlist; daí saem as vezes
+// reproduzido, a última reprodução, a janela "Histórico" e o sorteio "Menos tocados recentemente".
+use crate::util::write_atomic;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs;
+use std::io;
+use std::path::Path;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+const HISTORY_PATH: &str = "play_history.json";
+
+// Reproduções seguidas (tocar vários vídeos, pular de um em um) viram uma gravação só
+pub const SAVE_DELAY: Duration = Duration::from_secs(5);
+
+// As reproduções mais antigas saem quando o histórico passa disto
+const MAX_PLAYS: usize = 20_000;
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug)]
+pub struct Play {
+    pub id: String,
+    pub time: u64,                // Segundos Unix
+    pub title: String,            // Para mostrar mesmo com outra playlist aberta
+    pub playlist: Option<String>, // Arquivo ou URL da playlist de onde veio (None: colada)
+}
+
+// Resumo por vídeo, para a pesquisa e a ordenação não percorrerem o histórico todo
+#[derive(Clone, Copy, Default, PartialEq, Debug)]
+pub struct PlayStats {
+    pub count: u32,
+    pub last: u64,
+}
+
+impl PlayStats {
+    pub fn days_ago(&self, now: u64) -> f64 {
+        now.saturating_sub(self.last) as f64 / 86_400.0
+    }
+}
+
+// Default campo a campo: com #[serde(default)] no struct o serde moveria campos de um valor com Drop
+#[derive(Serialize, Deserialize, Default)]
+pub struct PlayHistory {
+    #[serde(default)]
+    pub paused: bool, // Não registrar novas reproduções
+    #[serde(default)]
+    plays: Vec<Play>, // Da mais antiga para a mais recente
+    #[serde(skip)]
+    stats: Arc<HashMap<String, PlayStats>>, // Compartilhado com a pesquisa em outra thread
+    #[serde(skip)]
+    unsaved_since: Option<Instant>, // Primeira reprodução ainda não gravada
+    #[serde(skip)]
+    read_only: bool, // O play_history.json existente não foi entendido: não sobrescrever
+}
+
+impl PlayHistory {
+    // Arquivo inexistente começa um histórico vazio; inválido ou ilegível é erro
+    pub fn load() -> Result<Self, String> {
+        let mut history: Self = match fs::read(HISTORY_PATH) {
+            Ok(bytes) => serde_json::from_slice(&bytes).map_err(|e| format!("{} inválido: {}", HISTORY_PATH, e))?,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Self::default(),
+            Err(e) => return Err(format!("Erro ao ler {}: {}", HISTORY_PATH, e)),
+        };
+        history.rebuild_stats();
+        Ok(history)
+    }
+
+    // Histórico vazio que nunca é gravado, para quando o arquivo não pôde ser lido
+    pub fn read_only() -> Self {
+        let mut history = Self::default();
+        history.read_only = true;
+        history
+    }
+
+    pub fn save(&mut self) -> io::Result<()> {
+        self.unsaved_since = None;
+        if self.read_only {
+            return Ok(());
+        }
+        let json = serde_json::to_vec(self)?;
+        write_atomic(Path::new(HISTORY_PATH), &json)
+    }
+
+    // Há reproduções esperando há SAVE_DELAY para serem gravadas
+    pub fn save_due(&self) -> bool {
+        self.unsaved_since.is_some_and(|since| since.elapsed() >= SAVE_DELAY)
+    }
+
+    fn rebuild_stats(&mut self) {
+        let mut stats: HashMap<String, PlayStats> = HashMap::new();
+        for play in &self.plays {
+            let entry = stats.entry(play.id.clone()).or_default();
+            entry.count += 1;
+            entry.last = entry.last.max(play.time);
+        }
+        self.stats = Arc::new(stats);
+    }
+
+    // Registra as reproduções; false se o histórico está pausado (nada muda)
+    pub fn record(&mut self, plays: Vec<Play>) -> bool {
+        if self.paused || plays.is_empty() {
+            return false;
+        }
+        let stats = Arc::make_mut(&mut self.stats);
+        for play in &plays {
+            let entry = stats.entry(play.id.clone()).or_default();
+            entry.count += 1;
+            entry.last = entry.last.max(play.time);
+        }
+        self.plays.extend(plays);
+        self.unsaved_since.get_or_insert_with(Instant::now);
+        if self.plays.len() > MAX_PLAYS {
+            let excess = self.plays.len() - MAX_PLAYS;
+            self.plays.drain(..excess);
+            self.rebuild_stats();
+        }
+        true
+    }
+
+    pub fn clear(&mut self) {
+        self.plays.clear();
+        self.stats = Arc::default();
+    }
+
+    pub fn plays(&self) -> &[Play] {
+        &self.plays
+    }
+
+    pub fn stats(&self) -> &Arc<HashMap<String, PlayStats>> {
+        &self.stats
+    }
+
+    pub fn play_count(&self, id: &str) -> u32 {
+        self.stats.get(id).map_or(0, |s| s.count)
+    }
+
+    pub fn last_played(&self, id: &str) -> Option<u64> {
+        self.stats.get(id).map(|s| s.last)
+    }
+}
+
+impl Drop for PlayHistory {
+    // Ao fechar o app, gravar as reproduções que ainda esperavam
+    fn drop(&mut self) {
+        if self.unsaved_since.is_some() {
+            let _ = self.save();
+        }
+    }
+}
+
+// "agora", "há 5 min", "há 3 h", "há 2 dias"
+pub fn ago(time: u64, now: u64) -> String {
+    let secs = now.saturating_sub(time);
+    match secs {
+        0..60 => "agora".to_string(),
+        60..3600 => format!("há {} min", secs / 60),
+        3600..86_400 => format!("há {} h", secs / 3600),
+        86_400..172_800 => "há 1 dia".to_string(),
+        _ => format!("há {} dias", secs / 86_400),
+    }
+}
@@ -0,0 +1,229 @@
+// Perfis de player externo: executável + modelo de argumentos
+use crate::m3u::HttpOptions;
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+use std::process::{Child, Command};
+
+// Marcadores aceitos no modelo de argumentos
+pub const PLACEHOLDERS_HELP: &str = "{playlist} arquivo .m3u temporário\n\
+{urls} cada URL como um argumento separado\n\
+{title} título do primeiro vídeo\n\
+{start} posição (a partir de 0) do vídeo inicial na playlist\n\
+{user_agent}, {referrer} do primeiro vídeo (#EXTVLCOPT)\n\
+{headers} cabeçalhos do primeiro vídeo no formato \"Nome: valor\" separados por vírgula\n\
+Num player aberto agora, esses cabeçalhos valem para toda a fila; só um mpv já\n\
+aberto (IPC) recebe os cabeçalhos de cada vídeo.\n\
+{repeat} \"inf\" quando \"Repetir\" está ligado na fila\n\
+Argumentos com marcadores vazios são omitidos.";
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug)]
+#[serde(default)]
+pub struct PlayerProfile {
+    pub name: String,
+    pub executable: String, // Vazio usa o aplicativo padrão do sistema para .m3u
+    pub args: String,       // Modelo de argumentos, separados por espaço (aspas agrupam)
+    pub ipc: bool,          // É um mpv controlado pelo IPC JSON (fila, destaque e controles)
+}
+
+impl Default for PlayerProfile {
+    fn default() -> Self {
+        Self {
+            name: "Novo player".to_string(),
+            executable: String::new(),
+            args: "{playlist}".to_string(),
+            ipc: false,
+        }
+    }
+}
+
+impl PlayerProfile {
+    pub fn system() -> Self {
+        Self {
+            name: "Padrão do sistema".to_string(),
+            executable: String::new(),
+            args: "{playlist}".to_string(),
+            ipc: false,
+        }
+    }
+
+    pub fn defaults() -> Vec<Self> {
+        vec![
+            Self::system(),
+            Self {
+                name: "mpv".to_string(),
+                executable: "mpv".to_string(),
+                args: "--playlist={playlist} --playlist-start={start} --loop-playlist={repeat} \
+                       --user-agent={user_agent} --referrer={referrer} --http-header-fields={headers}"
+                    .to_string(),
+                ipc: true,
+            },
+            Self {
+                name: "VLC".to_string(),
+                executable: "vlc".to_string(),
+                args: "{playlist}".to_string(),
+                ipc: false,
+            },
+        ]
+    }
+
+    // Inicia o player; retorna uma mensagem de erro se não for possível
+    pub fn launch(&self, launch: &Launch) -> Result<Child, String> {
+        let mut args = launch.extra_args.clone();
+        args.extend(expand_args(&self.args, launch));
+        if self.executable.trim().is_empty() {
+            return open_with_system(&args);
+        }
+        Command::new(self.executable.trim())
+            .args(&args)
+            .spawn()
+            .map_err(|e| format!("Não foi possível iniciar \"{}\": {}", self.executable.trim(), e))
+    }
+}
+
+// Dados de uma reprodução, usados para preencher os marcadores
+pub struct Launch<'a> {
+    pub playlist: &'a Path,
+    pub urls: Vec<&'a str>,
+    pub title: &'a str,
+    pub start: usize,
+    pub repeat: bool,                  // Repetir a playlist inteira
+    pub http: Option<&'a HttpOptions>, // Opções HTTP do primeiro vídeo
+    pub extra_args: Vec<String>,       // Argumentos adicionados pelo app antes do modelo
+}
+
+// Abrir com o aplicativo padrão do sistema (o primeiro argumento é o arquivo)
+fn open_with_system(args: &[String]) -> Result<Child, String> {
+    let target = args.first().ok_or("Nenhum arquivo para abrir")?;
+
+    #[cfg(target_os = "windows")]
+    let mut command = {
+        let mut command = Command::new("rundll32.exe");
+        command.args(["url.dll,FileProtocolHandler", target]);
+        command
+    };
+
+    #[cfg(target_os = "macos")]
+    let mut command = {
+        let mut command = Command::new("open");
+        command.arg(target);
+        command
+    };
+
+    #[cfg(not(any(target_os = "windows", target_os = "macos")))]
+    let mut command = {
+        let mut command = Command::new("xdg-open");
+        command.arg(target);
+        command
+    };
+
+    command
+        .spawn()
+        .map_err(|e| format!("Não foi possível abrir com o aplicativo padrão: {}", e))
+}
+
+// Mostra o arquivo no gerenciador de arquivos (no Linux abre a pasta, sem destacar o arquivo)
+pub fn reveal_in_file_manager(path: &Path) -> Result<(), String> {
+    #[cfg(target_os = "windows")]
+    let mut command = {
+        let mut command = Command::new("explorer.exe");
+        command.arg(format!("/select,{}", path.display()));
+        command
+    };
+
+    #[cfg(target_os = "macos")]
+    let mut command = {
+        let mut command = Command::new("open");
+        command.arg("-R").arg(path);
+        command
+    };
+
+    #[cfg(not(any(target_os = "windows", target_os = "macos")))]
+    let mut command = {
+        let mut command = Command::new("xdg-open");
+        command.arg(path.parent().unwrap_or(path));
+        command
+    };
+
+    command
+        .spawn()
+        .map(reap)
+        .map_err(|e| format!("Não foi possível abrir o gerenciador de arquivos: {}", e))
+}
+
+// Espera o processo terminar numa thread, para ele não ficar zumbi (Unix)
+pub fn reap(mut child: Child) {
+    std::thread::spawn(move || child.wait());
+}
+
+// Separa o modelo em argumentos e troca os marcadores
+fn expand_args(template: &str, launch: &Launch) -> Vec<String> {
+    let http = launch.http.cloned().unwrap_or_default();
+    let headers = http
+        .headers
+        .iter()
+        .map(|(name, value)| format!("{}: {}", name, value))
+        .collect::<Vec<_>>()
+        .join(",");
+    let start = launch.start.to_string();
+    let playlist = launch.playlist.display().to_string();
+    let repeat = if launch.repeat { "inf" } else { "" };
+    let values: [(&str, &str); 7] = [
+        ("{playlist}", &playlist),
+        ("{title}", launch.title),
+        ("{start}", &start),
+        ("{repeat}", repeat),
+        ("{user_agent}", http.user_agent.as_deref().unwrap_or("")),
+        ("{referrer}", http.referrer.as_deref().unwrap_or("")),
+        ("{headers}", &headers),
+    ];
+
+    let mut args = Vec::new();
+    for word in split_args(template) {
+        if word == "{urls}" {
+            args.extend(launch.urls.iter().map(|url| url.to_string()));
+            continue;
+        }
+        let mut arg = word.clone();
+        let mut empty_placeholder = false;
+        for (placeholder, value) in values {
+            if arg.contains(placeholder) {
+                empty_placeholder |= value.is_empty();
+                arg = arg.replace(placeholder, value);
+            }
+        }
+        if !empty_placeholder {
+            args.push(arg);
+        }
+    }
+    args
+}
+
+// Divide por espaços, respeitando trechos entre aspas duplas
+fn split_args(template: &str) -> Vec<String> {
+    let mut args = Vec::new();
+    let mut current = String::new();
+    let mut in_quotes = false;
+    let mut has_word = false;
+    for c in template.chars() {
+        match c {
+            '"' => {
+                in_quotes = !in_quotes;
+                has_word = true;
+            }
+            c if c.is_whitespace() && !in_quotes => {
+                if has_word {
+                    args.push(std::mem::take(&mut current));
+                    has_word = false;
+                }
+            }
+            c => {
+                current.push(c);
+                has_word = true;
+            }
+        }
+    }
+    if has_word {
+        args.push(current);
+    }
+    args
+}
@@ -0,0 +1,647 @@
+// Linguagem da pesquisa, usada pela barra de pesquisa e pela linha de comando (--query).
+//
+//   palavra "frase exata"     no título, sem diferenciar acentos e maiúsculas; cada palavra é
+//                             procurada de forma aproximada (fuzzy.rs), a frase só exata
+//   -palavra  -title:x        exclui
+//   a OR b    (a b) OR c      qualquer um dos lados; sem OR vale E
+//   title:x url:x group:x     campo contém
+//   tag:nome  tag:nome=valor  tag:nota>=8
+//   duration:>5m  duration:<1:30   (s, m, h ou mm:ss)
+//   played:<3                 vezes que o vídeo foi reproduzido
+//   lastplayed:>30d           última reprodução há mais de 30 dias (ou nunca); d, h ou w
+//   is:selected  is:missing   na fila / arquivo local que não existe mais
+use crate::fuzzy;
+use crate::metadata::Metadata;
+use crate::text::{self, Folded};
+use std::fmt;
+use std::path::{Path, PathBuf};
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct QueryError {
+    pub message: String,
+    pub position: usize, // Byte da consulta onde está o problema
+}
+
+impl fmt::Display for QueryError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} (posição {})", self.message, self.position + 1)
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Field {
+    Url,
+    Group,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Comparison {
+    Eq,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+}
+
+impl Comparison {
+    // ">=5m" -> (Ge, "5m"); sem operador vale igualdade
+    fn split(text: &str) -> (Comparison, &str) {
+        for (prefix, comparison) in [
+            (">=", Comparison::Ge),
+            ("<=", Comparison::Le),
+            (">", Comparison::Gt),
+            ("<", Comparison::Lt),
+            ("=", Comparison::Eq),
+        ] {
+            if let Some(rest) = text.strip_prefix(prefix) {
+                return (comparison, rest);
+            }
+        }
+        (Comparison::Eq, text)
+    }
+
+    fn test(self, a: f64, b: f64) -> bool {
+        match self {
+            Comparison::Eq => a == b,
+            Comparison::Lt => a < b,
+            Comparison::Le => a <= b,
+            Comparison::Gt => a > b,
+            Comparison::Ge => a >= b,
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Default)]
+pub enum Query {
+    #[default]
+    All, // Consulta vazia
+    Text(Vec<char>),   // Palavra (sem acentos, minúscula) procurada de forma aproximada no título
+    Phrase(Vec<char>), // Trecho exato do título
+    Field(Field, String), // Contém, sem acentos e minúsculo
+    Tag {
+        name: String,
+        value: Option<(Comparison, String)>,
+    },
+    Duration(Comparison, f64), // Segundos
+    Played(Comparison, f64),
+    LastPlayed(Comparison, f64), // Dias desde a última reprodução; nunca tocado conta como infinito
+    Selected,
+    Missing,
+    Not(Box<Query>),
+    And(Vec<Query>),
+    Or(Vec<Query>),
+}
+
+// Campos de um vídeo já preparados para a pesquisa, calculados uma vez por playlist carregada
+// (e não a cada tecla digitada)
+pub struct Searchable {
+    pub title: Folded,
+    pub url: String,           // Como está no arquivo, para is:missing
+    pub folded_url: String,    // Sem acentos e minúscula
+    pub group: Option<String>, // Sem acentos e minúsculo
+    pub duration: Option<f64>,
+    pub id: String,
+}
+
+impl Searchable {
+    pub fn new(title: &str, url: &str, group: Option<&str>, duration: Option<f64>, id: &str) -> Self {
+        Self {
+            title: Folded::new(title),
+            url: url.to_string(),
+            folded_url: text::fold(url),
+            group: group.map(text::fold),
+            duration,
+            id: id.to_string(),
+        }
+    }
+}
+
+// O que a consulta pode perguntar sobre um vídeo
+pub struct Item<'a> {
+    pub entry: &'a Searchable,
+    pub metadata: &'a Metadata,
+    pub play_count: u32,
+    pub played_days_ago: Option<f64>, // None: nunca reproduzido
+    pub selected: bool,
+    pub base_dir: Option<&'a Path>, // Pasta da playlist, para caminhos relativos
+}
+
+impl Query {
+    pub fn parse(text: &str) -> Result<Query, QueryError> {
+        let tokens = tokenize(text)?;
+        let mut parser = Parser { tokens, next: 0, end: text.len() };
+        if parser.tokens.is_empty() {
+            return Ok(Query::All);
+        }
+        let query = parser.expression()?;
+        match parser.tokens.get(parser.next) {
+            Some(token) => Err(error("')' sem '(' correspondente", token.position)),
+            None => Ok(query),
+        }
+    }
+
+    pub fn matches(&self, item: &Item) -> bool {
+        self.evaluate(item, &mut Vec::new()).is_some()
+    }
+
+    // A consulta procura texto no título, então os resultados podem ser ordenados pela pontuação
+    pub fn is_ranked(&self) -> bool {
+        match self {
+            Query::Text(_) | Query::Phrase(_) => true,
+            Query::And(queries) | Query::Or(queries) => queries.iter().any(Query::is_ranked),
+            _ => false,
+        }
+    }
+
+    // A consulta usa is:selected, então precisa ser refeita quando a seleção muda
+    pub fn uses_selection(&self) -> bool {
+        match self {
+            Query::Selected => true,
+            Query::Not(query) => query.uses_selection(),
+            Query::And(queries) | Query::Or(queries) => queries.iter().any(Query::uses_selection),
+            _ => false,
+        }
+    }
+
+    // Todo vídeo que atende esta consulta também atendia `previous` (uma palavra a mais, uma frase
+    // ou url:/group: mais longos), então basta procurar entre os resultados dela. Palavras soltas
+    // que crescem não contam: a tolerância a erros aumenta com o tamanho. Termos que dependem de
+    // algo que muda sem a consulta mudar (seleção, arquivos, reproduções) também não.
+    pub fn narrows(&self, previous: &Query) -> bool {
+        fn terms(query: &Query) -> &[Query] {
+            match query {
+                Query::All => &[],
+                Query::And(queries) => queries,
+                other => std::slice::from_ref(other),
+            }
+        }
+        fn volatile(query: &Query) -> bool {
+            match query {
+                Query::Selected | Query::Missing | Query::Played(..) | Query::LastPlayed(..) => true,
+                Query::Not(query) => volatile(query),
+                Query::And(queries) | Query::Or(queries) => queries.iter().any(volatile),
+                _ => false,
+            }
+        }
+        fn implies(term: &Query, previous: &Query) -> bool {
+            match (term, previous) {
+                (Query::Phrase(a), Query::Phrase(b)) => b.is_empty() || a.windows(b.len()).any(|w| w == b.as_slice()),
+                (Query::Field(f, a), Query::Field(g, b)) => f == g && a.contains(b.as_str()),
+                (a, b) => a == b,
+            }
+        }
+        let previous_terms = terms(previous);
+        !volatile(previous)
+            && previous_terms
+                .iter()
+                .all(|p| terms(self).iter().any(|term| implies(term, p)))
+    }
+
+    // Pontuação do vídeo (maior = mais parecido) e letras do título que casaram, para destacar.
+    // None se o vídeo não atende a consulta.
+    pub fn evaluate(&self, item: &Item, positions: &mut Vec<usize>) -> Option<i32> {
+        let pass = |ok: bool| ok.then_some(0);
+        match self {
+            Query::All => Some(0),
+            Query::Text(pattern) => {
+                let found = fuzzy::find(pattern, &item.entry.title)?;
+                positions.extend(found.positions);
+                Some(found.score)
+            }
+            Query::Phrase(phrase) => {
+                let title = &item.entry.title;
+                let start = title.find(phrase)?;
+                positions.extend(title.origin[start..start + phrase.len()].iter().copied());
+                // Vale como se todas as letras fossem seguidas
+                Some(phrase.len() as i32 * 24)
+            }
+            Query::Field(field, text) => {
+                let value = match field {
+                    Field::Url => Some(item.entry.folded_url.as_str()),
+                    Field::Group => item.entry.group.as_deref(),
+                };
+                pass(value.is_some_and(|v| v.contains(text.as_str())))
+            }
+            Query::Tag { name, value } => {
+                let current = item.metadata.tag.get(name).and_then(|ids| ids.get(&item.entry.id))?;
+                let Some((comparison, expected)) = value else {
+                    return Some(0);
+                };
+                let current = current.as_deref().unwrap_or("").trim();
+                pass(match (*comparison, number(current), number(expected)) {
+                    (_, Some(a), Some(b)) => comparison.test(a, b),
+                    (Comparison::Eq, _, _) => text::fold(current) == *expected,
+                    _ => false,
+                })
+            }
+            Query::Duration(comparison, seconds) => pass(item.entry.duration.is_some_and(|d| comparison.test(d, *seconds))),
+            Query::Played(comparison, count) => pass(comparison.test(item.play_count as f64, *count)),
+            Query::LastPlayed(comparison, days) => {
+                pass(comparison.test(item.played_days_ago.unwrap_or(f64::INFINITY), *days))
+            }
+            Query::Selected => pass(item.selected),
+            Query::Missing => pass(local_file(&item.entry.url, item.base_dir).is_some_and(|path| !path.exists())),
+            // O que foi excluído não é destacado
+            Query::Not(query) => pass(query.evaluate(item, &mut Vec::new()).is_none()),
+            Query::And(queries) => {
+                let mut total = 0;
+                for query in queries {
+                    total += query.evaluate(item, positions)?;
+                }
+                Some(total)
+            }
+            Query::Or(queries) => {
+                // Vale o lado com a melhor pontuação
+                let mut best: Option<(i32, Vec<usize>)> = None;
+                for query in queries {
+                    let mut found = Vec::new();
+                    if let Some(score) = query.evaluate(item, &mut found) {
+                        if best.as_ref().is_none_or(|(best_score, _)| score > *best_score) {
+                            best = Some((score, found));
+                        }
+                    }
+                }
+                let (score, found) = best?;
+                positions.extend(found);
+                Some(score)
+            }
+        }
+    }
+}
+
+// Caminho de uma entrada que é arquivo local (caminho ou file://); None para URLs da rede
+pub fn local_file(url: &str, base_dir: Option<&Path>) -> Option<PathBuf> {
+    let url = url.trim();
+    let path = if let Some(rest) = url.strip_prefix("file://") {
+        // file:///C:/... no Windows
+        let rest = rest
+            .strip_prefix('/')
+            .filter(|r| r.get(1..2) == Some(":"))
+            .unwrap_or(rest);
+        PathBuf::from(rest.replace("%20", " "))
+    } else if url.contains("://") {
+        return None;
+    } else {
+        PathBuf::from(url)
+    };
+    if path.is_relative() {
+        Some(base_dir?.join(path))
+    } else {
+        Some(path)
+    }
+}
+
+fn folded_chars(text: &str) -> Vec<char> {
+    text::fold(text).chars().collect()
+}
+
+fn error(message: &str, position: usize) -> QueryError {
+    QueryError { message: message.to_string(), position }
+}
+
+// "7,5" e "7.5" valem o mesmo
+fn number(text: &str) -> Option<f64> {
+    text.trim().replace(',', ".").parse().ok()
+}
+
+// "90", "90s", "5m", "1.5h", "1:30", "1:02:03" -> segundos
+fn duration_seconds(text: &str) -> Option<f64> {
+    if text.contains(':') {
+        let mut seconds = 0.0;
+        for part in text.split(':') {
+            seconds = seconds * 60.0 + number(part)?;
+        }
+        return Some(seconds);
+    }
+    let (digits, unit) = match text.char_indices().find(|(_, c)| c.is_alphabetic()) {
+        Some((i, _)) => (&text[..i], &text[i..]),
+        None => (text, "s"),
+    };
+    let scale = match unit.to_lowercase().as_str() {
+        "s" | "seg" => 1.0,
+        "m" | "min" => 60.0,
+        "h" => 3600.0,
+        _ => return None,
+    };
+    Some(number(digits)? * scale)
+}
+
+// "30", "30d", "12h", "2w" -> dias
+fn age_days(text: &str) -> Option<f64> {
+    let (digits, unit) = match text.char_indices().find(|(_, c)| c.is_alphabetic()) {
+        Some((i, _)) => (&text[..i], &text[i..]),
+        None => (text, "d"),
+    };
+    let scale = match unit.to_lowercase().as_str() {
+        "d" | "dia" | "dias" => 1.0,
+        "h" => 1.0 / 24.0,
+        "w" | "sem" => 7.0,
+        _ => return None,
+    };
+    Some(number(digits)? * scale)
+}
+
+enum TokenKind {
+    Open,        // (
+    Close,       // )
+    Or,
+    Term {
+        negate: bool,
+        text: String,
+        quoted: bool, // O termo inteiro estava entre aspas: texto puro, sem campo nem OR
+    },
+}
+
+struct Token {
+    kind: TokenKind,
+    position: usize,
+}
+
+fn tokenize(text: &str) -> Result<Vec<Token>, QueryError> {
+    let mut tokens = Vec::new();
+    let mut chars = text.char_indices().peekable();
+    while let Some(&(start, c)) = chars.peek() {
+        if c.is_whitespace() {
+            chars.next();
+            continue;
+        }
+        if c == '(' || c == ')' {
+            chars.next();
+            let kind = if c == '(' { TokenKind::Open } else { TokenKind::Close };
+            tokens.push(Token { kind, position: start });
+            continue;
+        }
+
+        // "-" só nega quando vem grudado no termo (ou num parêntese: -(a OR b))
+        let mut negate = false;
+        if c == '-' {
+            chars.next();
+            match chars.peek() {
+                Some(&(_, next)) if !next.is_whitespace() && next != ')' => negate = true,
+                _ => {
+                    tokens.push(Token { kind: TokenKind::Term { negate: false, text: "-".to_string(), quoted: false }, position: start });
+                    continue;
+                }
+            }
+            if chars.peek().is_some_and(|&(_, next)| next == '(') {
+                tokens.push(Token { kind: TokenKind::Term { negate: true, text: String::new(), quoted: false }, position: start });
+                continue;
+            }
+        }
+
+        let mut term = String::new();
+        let mut quoted = false;
+        let mut plain = false; // Houve algo fora das aspas
+        while let Some(&(i, c)) = chars.peek() {
+            if c.is_whitespace() || c == '(' || c == ')' {
+                break;
+            }
+            chars.next();
+            if c != '"' {
+                term.push(c);
+                plain = true;
+                continue;
+            }
+            quoted = true;
+            loop {
+                match chars.next() {
+                    Some((_, '"')) => break,
+                    Some((_, c)) => term.push(c),
+                    None => return Err(error("Aspas sem fechar", i)),
+                }
+            }
+        }
+
+        let kind = if term == "OR" && !quoted && !negate {
+            TokenKind::Or
+        } else {
+            TokenKind::Term { negate, text: term, quoted: quoted && !plain }
+        };
+        tokens.push(Token { kind, position: start });
+    }
+    Ok(tokens)
+}
+
+struct Parser {
+    tokens: Vec<Token>,
+    next: usize,
+    end: usize, // Tamanho da consulta, para erros no fim
+}
+
+impl Parser {
+    fn position(&self) -> usize {
+        self.tokens.get(self.next).map_or(self.end, |t| t.position)
+    }
+
+    // a OR b OR c
+    fn expression(&mut self) -> Result<Query, QueryError> {
+        let mut alternatives = vec![self.sequence()?];
+        while matches!(self.tokens.get(self.next), Some(Token { kind: TokenKind::Or, .. })) {
+            self.next += 1;
+            alternatives.push(self.sequence()?);
+        }
+        Ok(if alternatives.len() == 1 { alternatives.remove(0) } else { Query::Or(alternatives) })
+    }
+
+    // Termos seguidos: todos precisam valer
+    fn sequence(&mut self) -> Result<Query, QueryError> {
+        let mut terms = Vec::new();
+        while let Some(token) = self.tokens.get(self.next) {
+            match token.kind {
+                TokenKind::Or | TokenKind::Close => break,
+                _ => terms.push(self.unary()?),
+            }
+        }
+        match terms.len() {
+            0 => Err(error("Falta um termo antes ou depois do OR", self.position())),
+            1 => Ok(terms.remove(0)),
+            _ => Ok(Query::And(terms)),
+        }
+    }
+
+    fn unary(&mut self) -> Result<Query, QueryError> {
+        let position = self.position();
+        let token = &self.tokens[self.next];
+        self.next += 1;
+        match &token.kind {
+            TokenKind::Open => {
+                let query = self.expression()?;
+                match self.tokens.get(self.next) {
+                    Some(Token { kind: TokenKind::Close, .. }) => {
+                        self.next += 1;
+                        Ok(query)
+                    }
+                    _ => Err(error("Parêntese sem fechar", position)),
+                }
+            }
+            // "-(" : nega o grupo que vem a seguir
+            TokenKind::Term { negate: true, text, quoted: false } if text.is_empty() => {
+                Ok(Query::Not(Box::new(self.unary()?)))
+            }
+            TokenKind::Term { negate, text, quoted } => {
+                let start = position + usize::from(*negate);
+                let term = if *quoted { Query::Phrase(folded_chars(text)) } else { term(text, start)? };
+                Ok(if *negate { Query::Not(Box::new(term)) } else { term })
+            }
+            TokenKind::Close => Err(error("')' sem '(' correspondente", position)),
+            TokenKind::Or => Err(error("Falta um termo antes do OR", position)),
+        }
+    }
+}
+
+// Um termo: "campo:valor" para os campos conhecidos, senão texto do título
+// (assim uma URL colada como "https://..." continua sendo só texto)
+fn term(text: &str, position: usize) -> Result<Query, QueryError> {
+    let Some((field, value)) = text.split_once(':') else {
+        return Ok(Query::Text(folded_chars(text)));
+    };
+    let field = field.to_lowercase();
+    let known = [
+        "title", "titulo", "título", "url", "group", "grupo", "tag", "duration", "duracao", "duração", "played",
+        "tocado", "lastplayed", "ultima", "última", "is",
+    ];
+    if !known.contains(&field.as_str()) {
+        return Ok(Query::Text(folded_chars(text)));
+    }
+    let value_position = position + field.len() + 1;
+    if value.is_empty() {
+        return Err(error(&format!("Falta o valor depois de {}:", field), value_position));
+    }
+
+    Ok(match field.as_str() {
+        // title:"duas palavras" procura o trecho exato
+        "title" | "titulo" | "título" if value.contains(char::is_whitespace) => Query::Phrase(folded_chars(value)),
+        "title" | "titulo" | "título" => Query::Text(folded_chars(value)),
+        "url" => Query::Field(Field::Url, text::fold(value)),
+        "group" | "grupo" => Query::Field(Field::Group, text::fold(value)),
+        "tag" => match value.find(['=', '<', '>']) {
+            Some(i) => {
+                let (comparison, expected) = Comparison::split(&value[i..]);
+                Query::Tag {
+                    name: value[..i].to_string(),
+                    value: Some((comparison, text::fold(expected.trim()))),
+                }
+            }
+            None => Query::Tag { name: value.to_string(), value: None },
+        },
+        "duration" | "duracao" | "duração" => {
+            let (comparison, amount) = Comparison::split(value);
+            let seconds = duration_seconds(amount)
+                .ok_or_else(|| error("Duração inválida (use 90s, 5m, 1h ou 1:30)", value_position))?;
+            Query::Duration(comparison, seconds)
+        }
+        "played" | "tocado" => {
+            let (comparison, amount) = Comparison::split(value);
+            let count = amount
+                .parse::<u32>()
+                .map_err(|_| error("Número de reproduções inválido", value_position))?;
+            Query::Played(comparison, count as f64)
+        }
+        "lastplayed" | "ultima" | "última" => {
+            let (comparison, amount) = Comparison::split(value);
+            let days = age_days(amount).ok_or_else(|| error("Tempo inválido (use 30d, 12h ou 2w)", value_position))?;
+            Query::LastPlayed(comparison, days)
+        }
+        _ => match value.to_lowercase().as_str() {
+            "selected" | "selecionado" => Query::Selected,
+            "missing" | "ausente" => Query::Missing,
+            _ => return Err(error("Use is:selected ou is:missing", value_position)),
+        },
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn text(word: &str) -> Query {
+        Query::Text(folded_chars(word))
+    }
+
+    fn error_at(query: &str) -> (String, usize) {
+        let error = Query::parse(query).unwrap_err();
+        (error.message, error.position)
+    }
+
+    #[test]
+    fn aspas_negacao_e_precedencia_do_or() {
+        assert_eq!(
+            Query::parse(r#""Ação Final" b"#).unwrap(),
+            Query::And(vec![Query::Phrase(folded_chars("acao final")), text("b")])
+        );
+        // O OR separa sequências inteiras: "a b OR c" é (a b) OR c
+        assert_eq!(
+            Query::parse("a b OR c").unwrap(),
+            Query::Or(vec![Query::And(vec![text("a"), text("b")]), text("c")])
+        );
+        assert_eq!(
+            Query::parse("-(a OR b) c").unwrap(),
+            Query::And(vec![Query::Not(Box::new(Query::Or(vec![text("a"), text("b")]))), text("c")])
+        );
+        // "-" solto e "OR" entre aspas ou minúsculo são só texto
+        assert_eq!(Query::parse("a - b").unwrap(), Query::And(vec![text("a"), text("-"), text("b")]));
+        assert_eq!(Query::parse(r#"a "OR" or"#).unwrap(), Query::And(vec![text("a"), Query::Phrase(folded_chars("or")), text("or")]));
+        assert_eq!(Query::parse("   ").unwrap(), Query::All);
+    }
+
+    #[test]
+    fn posicao_dos_erros() {
+        assert_eq!(error_at(r#"abc "de"#), ("Aspas sem fechar".to_string(), 4));
+        assert_eq!(error_at("x (a b"), ("Parêntese sem fechar".to_string(), 2));
+        assert_eq!(error_at("a )"), ("')' sem '(' correspondente".to_string(), 2));
+        assert_eq!(error_at("a OR"), ("Falta um termo antes ou depois do OR".to_string(), 4));
+        assert_eq!(error_at("OR a").1, 0);
+        assert_eq!(error_at("x duration:abc").1, 11);
+        assert_eq!(error_at("-duration:abc").1, 10); // Conta o "-"
+        assert_eq!(error_at("is:").1, 3);
+        // O Display mostra a posição contando a partir de 1
+        assert_eq!(Query::parse("a )").unwrap_err().to_string(), "')' sem '(' correspondente (posição 3)");
+    }
+
+    #[test]
+    fn campos() {
+        assert_eq!(Query::parse("duration:>=1:30").unwrap(), Query::Duration(Comparison::Ge, 90.0));
+        assert_eq!(Query::parse("duração:<5m").unwrap(), Query::Duration(Comparison::Lt, 300.0));
+        assert_eq!(Query::parse("duration:1,5h").unwrap(), Query::Duration(Comparison::Eq, 5400.0));
+        assert_eq!(
+            Query::parse("tag:nota>=8").unwrap(),
+            Query::Tag { name: "nota".to_string(), value: Some((Comparison::Ge, "8".to_string())) }
+        );
+        assert_eq!(Query::parse("tag:ver").unwrap(), Query::Tag { name: "ver".to_string(), value: None });
+        assert_eq!(Query::parse(r#"title:"a b""#).unwrap(), Query::Phrase(folded_chars("a b")));
+        assert_eq!(Query::parse("title:abc").unwrap(), text("abc"));
+        assert_eq!(Query::parse("is:SELECTED").unwrap(), Query::Selected);
+        // Campo desconhecido (ou uma URL) é texto do título
+        assert_eq!(Query::parse("https://x.com").unwrap(), text("https://x.com"));
+    }
+
+    #[test]
+    fn avalia_tags_duracao_e_trechos() {
+        let mut metadata = Metadata::default();
+        let notas = metadata.tag.entry("nota".to_string()).or_default();
+        notas.insert("a".to_string(), Some("8,5".to_string()));
+        notas.insert("b".to_string(), Some("7".to_string()));
+        let a = Searchable::new("Ação Final", "http://a", None, Some(120.0), "a");
+        let b = Searchable::new("Outro", "http://b", None, None, "b");
+        let item = |entry| Item { entry, metadata: &metadata, play_count: 0, played_days_ago: None, selected: false, base_dir: None };
+        let matches = |query: &str, entry| Query::parse(query).unwrap().matches(&item(entry));
+
+        assert!(matches("tag:nota>=8", &a));
+        assert!(!matches("tag:nota>=8", &b));
+        assert!(matches("tag:nota", &b));
+        assert!(matches("duration:>1m", &a));
+        assert!(!matches("-duration:>1m", &a));
+        assert!(!matches("duration:<1m", &b)); // Sem duração conhecida não passa em nenhuma comparação
+        assert!(matches("is:selected OR outro", &b));
+
+        let mut positions = Vec::new();
+        Query::parse(r#""cao f""#).unwrap().evaluate(&item(&a), &mut positions).unwrap();
+        assert_eq!(positions, [1, 2, 3, 4, 5]); // "ção F" no título original
+    }
+
+    #[test]
+    fn selecao_na_consulta() {
+        assert!(Query::parse("a OR -(b is:selected)").unwrap().uses_selection());
+        assert!(!Query::parse("a OR b").unwrap().uses_selection());
+    }
+}
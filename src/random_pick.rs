@@ -0,0 +1,152 @@
+// Sorteio de vídeos ("Sortear") com vários modos
+use crate::util::Rng;
+use std::collections::{HashMap, HashSet};
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum PickMode {
+    Uniform,            // N vídeos quaisquer
+    WeightedRating,     // Vídeos com nota maior saem mais
+    LeastRecentlyPlayed, // Os que não tocam há mais tempo primeiro
+    NoRepeat,           // Não repete até esgotar todos os vídeos do filtro
+    SpreadArtists,      // Evita o mesmo artista/grupo em sequência
+}
+
+impl PickMode {
+    pub const ALL: [PickMode; 5] = [
+        PickMode::Uniform,
+        PickMode::WeightedRating,
+        PickMode::LeastRecentlyPlayed,
+        PickMode::NoRepeat,
+        PickMode::SpreadArtists,
+    ];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            PickMode::Uniform => "Aleatório",
+            PickMode::WeightedRating => "Pela nota",
+            PickMode::LeastRecentlyPlayed => "Menos tocados recentemente",
+            PickMode::NoRepeat => "Sem repetir até esgotar",
+            PickMode::SpreadArtists => "Espalhar artistas",
+        }
+    }
+}
+
+// Um vídeo que pode ser sorteado, com os dados usados pelos modos
+pub struct Candidate {
+    pub index: usize,             // Índice em `videos`
+    pub id: String,
+    pub rating: Option<f64>,      // Nota (maior = melhor)
+    pub last_played: Option<u64>, // Última reprodução em segundos Unix
+    pub artist: String,           // Chave de artista/grupo
+}
+
+// Sorteia até `count` vídeos e retorna os índices na ordem de reprodução.
+// `drawn` guarda os IDs já sorteados no modo NoRepeat entre uma chamada e outra.
+pub fn pick(
+    mut candidates: Vec<Candidate>,
+    count: usize,
+    mode: PickMode,
+    drawn: &mut HashSet<String>,
+    rng: &mut Rng,
+) -> Vec<usize> {
+    let count = count.min(candidates.len());
+    rng.shuffle(&mut candidates);
+
+    let picked: Vec<Candidate> = match mode {
+        PickMode::Uniform => candidates.into_iter().take(count).collect(),
+        PickMode::WeightedRating => weighted(candidates, count, rng),
+        PickMode::LeastRecentlyPlayed => {
+            // Ordenação estável depois do embaralhamento: empates ficam em ordem aleatória
+            candidates.sort_by_key(|c| c.last_played.unwrap_or(0));
+            candidates.into_iter().take(count).collect()
+        }
+        PickMode::NoRepeat => no_repeat(candidates, count, drawn),
+        PickMode::SpreadArtists => {
+            let chosen = candidates.into_iter().take(count).collect();
+            spread_artists(chosen, rng)
+        }
+    };
+
+    picked.into_iter().map(|c| c.index).collect()
+}
+
+// Amostragem ponderada sem reposição (Efraimidis–Spirakis): chave = u^(1/peso), maiores ganham.
+// Vídeos sem nota recebem a média das notas existentes.
+fn weighted(candidates: Vec<Candidate>, count: usize, rng: &mut Rng) -> Vec<Candidate> {
+    let rated: Vec<f64> = candidates.iter().filter_map(|c| c.rating).collect();
+    let default_weight = if rated.is_empty() {
+        1.0
+    } else {
+        rated.iter().sum::<f64>() / rated.len() as f64
+    };
+
+    let mut keyed: Vec<(f64, Candidate)> = candidates
+        .into_iter()
+        .map(|c| {
+            let weight = c.rating.unwrap_or(default_weight).max(0.01);
+            let u = (rng.next_u64() >> 11) as f64 / (1u64 << 53) as f64;
+            (u.powf(1.0 / weight), c)
+        })
+        .collect();
+    keyed.sort_by(|a, b| b.0.total_cmp(&a.0));
+    keyed.into_iter().take(count).map(|(_, c)| c).collect()
+}
+
+fn no_repeat(candidates: Vec<Candidate>, count: usize, drawn: &mut HashSet<String>) -> Vec<Candidate> {
+    let (fresh, used): (Vec<Candidate>, Vec<Candidate>) =
+        candidates.into_iter().partition(|c| !drawn.contains(&c.id));
+
+    let mut picked: Vec<Candidate> = fresh.into_iter().collect();
+    if picked.len() >= count {
+        picked.truncate(count);
+    } else {
+        // Todos foram sorteados: começar uma nova rodada com os que ficaram de fora desta vez
+        drawn.clear();
+        let missing = count - picked.len();
+        picked.extend(used.into_iter().take(missing));
+    }
+
+    drawn.extend(picked.iter().map(|c| c.id.clone()));
+    picked
+}
+
+// Reordena para afastar vídeos do mesmo artista: a cada passo escolhe o artista com mais
+// vídeos restantes que não seja o anterior (empates decididos pela ordem já embaralhada)
+fn spread_artists(chosen: Vec<Candidate>, rng: &mut Rng) -> Vec<Candidate> {
+    let mut order: Vec<String> = Vec::new();
+    let mut groups: HashMap<String, Vec<Candidate>> = HashMap::new();
+    for candidate in chosen {
+        if !groups.contains_key(&candidate.artist) {
+            order.push(candidate.artist.clone());
+        }
+        groups.entry(candidate.artist.clone()).or_default().push(candidate);
+    }
+    rng.shuffle(&mut order);
+
+    let mut result = Vec::new();
+    let mut last: Option<String> = None;
+    loop {
+        let next = order
+            .iter()
+            .filter(|artist| !groups[*artist].is_empty())
+            .filter(|artist| last.as_ref() != Some(*artist))
+            .max_by_key(|artist| groups[*artist].len())
+            .or_else(|| order.iter().find(|artist| !groups[*artist].is_empty()))
+            .cloned();
+        let Some(artist) = next else { break };
+        if let Some(candidate) = groups.get_mut(&artist).and_then(Vec::pop) {
+            result.push(candidate);
+        }
+        last = Some(artist);
+    }
+    result
+}
+
+// Chave de artista: group-title quando existir, senão o trecho antes de " - " no título
+pub fn artist_key(title: &str, group: Option<&str>) -> String {
+    let key = match group {
+        Some(group) if !group.trim().is_empty() => group,
+        _ => title.split(" - ").next().unwrap_or(title),
+    };
+    key.trim().to_lowercase()
+}
@@ -0,0 +1,177 @@
+// Pesquisa da barra de pesquisa fora da thread da interface, para playlists grandes (IPTV com
+// dezenas de milhares de canais). Os campos dos vídeos ficam num índice montado uma vez por
+// playlist; a pesquisa só começa depois de uma pausa na digitação, cada pesquisa nova abandona a
+// anterior e, quando a consulta só restringe a última (Query::narrows), procura apenas entre os
+// resultados dela.
+use crate::metadata::Metadata;
+use crate::play_history::PlayStats;
+use crate::query::{Item, Query, Searchable};
+use std::collections::{HashMap, HashSet};
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::mpsc::{self, Receiver, TryRecvError};
+use std::sync::Arc;
+use std::thread;
+use std::time::{Duration, Instant};
+
+// Espera depois da última tecla antes de pesquisar
+const DEBOUNCE: Duration = Duration::from_millis(150);
+
+// De quantos em quantos vídeos a thread confere se a pesquisa ainda é a mais recente
+const CANCEL_CHECK: usize = 512;
+
+// Vídeo que atende a consulta
+pub struct Hit {
+    pub index: usize,        // Índice em videos
+    pub score: i32,
+    pub positions: Vec<usize>, // Letras do título para destacar
+}
+
+// O que a consulta pode perguntar além dos campos do índice; uma cópia vai para a thread
+pub struct Context {
+    pub metadata: Arc<Metadata>,
+    pub selected: HashSet<usize>,
+    pub base_dir: Option<PathBuf>,
+    pub plays: Arc<HashMap<String, PlayStats>>, // Por ID do vídeo
+    pub now: u64,
+}
+
+#[derive(Default)]
+pub struct Searcher {
+    index: Arc<Vec<Searchable>>,
+    previous: Option<(Query, Arc<Vec<usize>>)>, // Última consulta pesquisada e os vídeos que a atenderam
+    generation: Arc<AtomicU64>,                 // Número da pesquisa mais recente; as outras desistem
+    due: Option<Instant>,                       // Pesquisa esperando a pausa na digitação
+    results: Option<Receiver<(Query, Vec<Hit>)>>,
+}
+
+impl Searcher {
+    // Monta o índice de novo (playlist carregada ou editada)
+    pub fn rebuild(&mut self, entries: Vec<Searchable>) {
+        self.cancel();
+        self.index = Arc::new(entries);
+        self.previous = None;
+    }
+
+    // Abandona a pesquisa adiada ou em andamento
+    pub fn cancel(&mut self) {
+        self.generation.fetch_add(1, Ordering::Relaxed);
+        self.due = None;
+        self.results = None;
+    }
+
+    // Pesquisa já, nesta thread: só ao abrir uma playlist, quando ainda não há resultados para
+    // mostrar. Depois disso as mudanças nos dados passam por refresh.
+    pub fn search_now(&mut self, query: &Query, context: &Context) -> Vec<Hit> {
+        self.cancel();
+        let hits = search(query, &self.index, None, context, || true).unwrap_or_default();
+        self.previous = Some((query.clone(), Arc::new(hits.iter().map(|hit| hit.index).collect())));
+        hits
+    }
+
+    // Vídeos que atendem uma consulta qualquer (pesquisa salva), sem mexer na pesquisa da barra
+    pub fn evaluate(&self, query: &Query, context: &Context) -> Vec<Hit> {
+        search(query, &self.index, None, context, || true).unwrap_or_default()
+    }
+
+    // A consulta mudou pela digitação: pesquisa quando o usuário parar de digitar
+    pub fn schedule(&mut self, ctx: &egui::Context) {
+        self.due = Some(Instant::now() + DEBOUNCE);
+        ctx.request_repaint_after(DEBOUNCE);
+    }
+
+    pub fn is_due(&self) -> bool {
+        self.due.is_some_and(|due| Instant::now() >= due)
+    }
+
+    pub fn is_busy(&self) -> bool {
+        self.due.is_some() || self.results.is_some()
+    }
+
+    // Começa a pesquisa adiada numa thread; o resultado sai em take_results
+    pub fn start(&mut self, query: &Query, context: Context, ctx: &egui::Context) {
+        self.cancel();
+        let generation = self.generation.load(Ordering::Relaxed);
+        let candidates = self
+            .previous
+            .as_ref()
+            .filter(|(previous, _)| query.narrows(previous))
+            .map(|(_, matched)| Arc::clone(matched));
+
+        let (tx, rx) = mpsc::channel();
+        self.results = Some(rx);
+        let index = Arc::clone(&self.index);
+        let current = Arc::clone(&self.generation);
+        let query = query.clone();
+        let ctx = ctx.clone();
+        thread::spawn(move || {
+            let still_wanted = || current.load(Ordering::Relaxed) == generation;
+            if let Some(hits) = search(&query, &index, candidates.as_deref().map(Vec::as_slice), &context, still_wanted) {
+                let _ = tx.send((query, hits));
+                ctx.request_repaint();
+            }
+        });
+    }
+
+    // Os dados mudaram (tags, reproduções, playlist editada, seleção com is:selected): pesquisa
+    // de novo já, numa thread, entre todos os vídeos, porque os resultados anteriores podem não
+    // valer mais
+    pub fn refresh(&mut self, query: &Query, context: Context, ctx: &egui::Context) {
+        self.previous = None;
+        self.start(query, context, ctx);
+    }
+
+    // Resultados da pesquisa em andamento, quando ela termina
+    pub fn take_results(&mut self) -> Option<Vec<Hit>> {
+        let received = self.results.as_ref()?.try_recv();
+        match received {
+            Ok((query, hits)) => {
+                self.results = None;
+                self.previous = Some((query, Arc::new(hits.iter().map(|hit| hit.index).collect())));
+                Some(hits)
+            }
+            Err(TryRecvError::Empty) => None,
+            Err(TryRecvError::Disconnected) => {
+                self.results = None;
+                None
+            }
+        }
+    }
+}
+
+// Vídeos que atendem a consulta, na ordem do arquivo; None se a pesquisa foi abandonada
+fn search(
+    query: &Query,
+    index: &[Searchable],
+    candidates: Option<&[usize]>,
+    context: &Context,
+    still_wanted: impl Fn() -> bool,
+) -> Option<Vec<Hit>> {
+    let indices: Vec<usize> = match candidates {
+        Some(candidates) => candidates.to_vec(),
+        None => (0..index.len()).collect(),
+    };
+    let mut hits = Vec::new();
+    for (n, i) in indices.into_iter().enumerate() {
+        if n % CANCEL_CHECK == 0 && !still_wanted() {
+            return None;
+        }
+        let entry = &index[i];
+        let stats = context.plays.get(&entry.id);
+        let item = Item {
+            entry,
+            metadata: &context.metadata,
+            play_count: stats.map_or(0, |s| s.count),
+            played_days_ago: stats.map(|s| s.days_ago(context.now)),
+            selected: context.selected.contains(&i),
+            base_dir: context.base_dir.as_deref(),
+        };
+        let mut positions = Vec::new();
+        if let Some(score) = query.evaluate(&item, &mut positions) {
+            positions.sort_unstable();
+            positions.dedup();
+            hits.push(Hit { index: i, score, positions });
+        }
+    }
+    Some(hits)
+}
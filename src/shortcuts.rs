@@ -0,0 +1,239 @@
+// Atalhos de teclado remapeáveis, guardados em config.json como texto ("Ctrl+Shift+A")
+use egui::{Key, KeyboardShortcut, Modifiers};
+use serde::{Deserialize, Serialize};
+
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Action {
+    MoveLeft,
+    MoveRight,
+    MoveUp,
+    MoveDown,
+    PageUp,
+    PageDown,
+    First,
+    Last,
+    ToggleSelection,
+    Play,
+    FocusSearch,
+    ClearSearch,
+    SelectAll,
+    ClearSelection,
+    Save,
+    RemoveEntries,
+    MoveEntriesUp,
+    MoveEntriesDown,
+    Undo,
+    Redo,
+}
+
+impl Action {
+    pub const ALL: [Action; 20] = [
+        Action::MoveLeft,
+        Action::MoveRight,
+        Action::MoveUp,
+        Action::MoveDown,
+        Action::PageUp,
+        Action::PageDown,
+        Action::First,
+        Action::Last,
+        Action::ToggleSelection,
+        Action::Play,
+        Action::FocusSearch,
+        Action::ClearSearch,
+        Action::SelectAll,
+        Action::ClearSelection,
+        Action::Save,
+        Action::RemoveEntries,
+        Action::MoveEntriesUp,
+        Action::MoveEntriesDown,
+        Action::Undo,
+        Action::Redo,
+    ];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Action::MoveLeft => "Mover para a esquerda",
+            Action::MoveRight => "Mover para a direita",
+            Action::MoveUp => "Mover para cima",
+            Action::MoveDown => "Mover para baixo",
+            Action::PageUp => "Página acima",
+            Action::PageDown => "Página abaixo",
+            Action::First => "Primeiro vídeo",
+            Action::Last => "Último vídeo",
+            Action::ToggleSelection => "Selecionar/desmarcar",
+            Action::Play => "Reproduzir",
+            Action::FocusSearch => "Ir para a pesquisa",
+            Action::ClearSearch => "Limpar pesquisa",
+            Action::SelectAll => "Selecionar resultados da pesquisa",
+            Action::ClearSelection => "Limpar seleção",
+            Action::Save => "Salvar playlist",
+            Action::RemoveEntries => "Remover da playlist",
+            Action::MoveEntriesUp => "Subir na playlist",
+            Action::MoveEntriesDown => "Descer na playlist",
+            Action::Undo => "Desfazer",
+            Action::Redo => "Refazer",
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug)]
+pub struct Shortcut {
+    pub action: Action,
+    pub keys: String, // Ex.: "Ctrl+F", "Shift+End", "/"
+}
+
+impl Shortcut {
+    pub fn new(action: Action, keys: &str) -> Self {
+        Self { action, keys: keys.to_string() }
+    }
+
+    pub fn defaults() -> Vec<Self> {
+        vec![
+            Self::new(Action::MoveLeft, "Left"),
+            Self::new(Action::MoveRight, "Right"),
+            Self::new(Action::MoveUp, "Up"),
+            Self::new(Action::MoveDown, "Down"),
+            Self::new(Action::PageUp, "PageUp"),
+            Self::new(Action::PageDown, "PageDown"),
+            Self::new(Action::First, "Home"),
+            Self::new(Action::Last, "End"),
+            Self::new(Action::ToggleSelection, "Space"),
+            Self::new(Action::Play, "Enter"),
+            Self::new(Action::FocusSearch, "/"),
+            Self::new(Action::FocusSearch, "Ctrl+F"),
+            Self::new(Action::ClearSearch, "Escape"),
+            Self::new(Action::SelectAll, "Ctrl+A"),
+            Self::new(Action::ClearSelection, "Ctrl+Shift+A"),
+            Self::new(Action::Save, "Ctrl+S"),
+            Self::new(Action::RemoveEntries, "Delete"),
+            Self::new(Action::MoveEntriesUp, "Alt+Up"),
+            Self::new(Action::MoveEntriesDown, "Alt+Down"),
+            Self::new(Action::Undo, "Ctrl+Z"),
+            Self::new(Action::Redo, "Ctrl+Shift+Z"),
+            Self::new(Action::Redo, "Ctrl+Y"),
+        ]
+    }
+
+    pub fn shortcut(&self) -> Option<KeyboardShortcut> {
+        parse(&self.keys)
+    }
+
+    // Ações que não aparecem nos atalhos salvos (criadas depois que o config.json foi gravado)
+    // ganham os atalhos padrão. Uma ação sem atalho de propósito fica salva com as teclas vazias.
+    pub fn with_missing_defaults(mut saved: Vec<Self>) -> Vec<Self> {
+        let missing: Vec<Self> = Self::defaults()
+            .into_iter()
+            .filter(|default| !saved.iter().any(|s| s.action == default.action))
+            .collect();
+        saved.extend(missing);
+        saved
+    }
+}
+
+// "Ctrl+Shift+A" -> atalho do egui; Ctrl vale como Cmd no macOS
+pub fn parse(text: &str) -> Option<KeyboardShortcut> {
+    // Sem teclas: a ação está sem atalho (e não "+")
+    if text.trim().is_empty() {
+        return None;
+    }
+    let mut modifiers = Modifiers::NONE;
+    let mut key = None;
+    for part in text.split('+').map(str::trim) {
+        match part.to_ascii_lowercase().as_str() {
+            "ctrl" | "cmd" => modifiers |= Modifiers::COMMAND,
+            "shift" => modifiers |= Modifiers::SHIFT,
+            "alt" => modifiers |= Modifiers::ALT,
+            // "+" sozinho (ou "Ctrl++") deixa uma parte vazia
+            "" => key = Some(Key::Plus),
+            _ => key = Some(Key::from_name(part)?),
+        }
+    }
+    Some(KeyboardShortcut::new(modifiers, key?))
+}
+
+pub fn format(shortcut: &KeyboardShortcut) -> String {
+    let mut text = String::new();
+    if shortcut.modifiers.command || shortcut.modifiers.ctrl || shortcut.modifiers.mac_cmd {
+        text.push_str("Ctrl+");
+    }
+    if shortcut.modifiers.shift {
+        text.push_str("Shift+");
+    }
+    if shortcut.modifiers.alt {
+        text.push_str("Alt+");
+    }
+    // Setas pelo nome (o símbolo ⏴ é difícil de digitar no config.json); pontuação pelo símbolo
+    let key = shortcut.logical_key;
+    match key {
+        Key::ArrowLeft | Key::ArrowRight | Key::ArrowUp | Key::ArrowDown => text.push_str(key.name()),
+        _ => text.push_str(key.symbol_or_name()),
+    }
+    text
+}
+
+// Ações cujos atalhos foram pressionados neste quadro.
+// Os atalhos com mais modificadores são testados antes (Ctrl+Shift+A antes de Ctrl+A),
+// porque o egui ignora Shift/Alt extras ao comparar.
+pub fn pressed(ctx: &egui::Context, shortcuts: &[Shortcut]) -> Vec<Action> {
+    let mut parsed: Vec<(Action, KeyboardShortcut)> = shortcuts
+        .iter()
+        .filter_map(|s| Some((s.action, s.shortcut()?)))
+        .collect();
+    parsed.sort_by_key(|(_, s)| {
+        let m = s.modifiers;
+        std::cmp::Reverse(m.command as u8 + m.shift as u8 + m.alt as u8)
+    });
+
+    ctx.input_mut(|input| {
+        parsed
+            .iter()
+            .filter(|(_, shortcut)| input.consume_shortcut(shortcut))
+            .map(|(action, _)| *action)
+            .collect()
+    })
+}
+
+// Primeira tecla pressionada neste quadro, para gravar um novo atalho
+pub fn capture(ctx: &egui::Context) -> Option<KeyboardShortcut> {
+    ctx.input(|input| {
+        input.events.iter().find_map(|event| match event {
+            egui::Event::Key { key, pressed: true, modifiers, .. } => {
+                Some(KeyboardShortcut::new(*modifiers, *key))
+            }
+            _ => None,
+        })
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn acoes_novas_ganham_os_atalhos_padrao() {
+        // config.json de antes de Salvar/Desfazer existirem, com um atalho trocado
+        let saved = vec![Shortcut::new(Action::Play, "P"), Shortcut::new(Action::FocusSearch, "")];
+        let merged = Shortcut::with_missing_defaults(saved);
+
+        let keys = |action| -> Vec<&str> {
+            merged.iter().filter(|s| s.action == action).map(|s| s.keys.as_str()).collect()
+        };
+        assert_eq!(keys(Action::Play), ["P"]); // Mantém a escolha do usuário
+        assert_eq!(keys(Action::FocusSearch), [""]); // Sem atalho de propósito
+        assert_eq!(keys(Action::Save), ["Ctrl+S"]);
+        assert_eq!(keys(Action::Undo), ["Ctrl+Z"]);
+        assert_eq!(keys(Action::Redo), ["Ctrl+Shift+Z", "Ctrl+Y"]);
+        assert!(Action::ALL.iter().all(|&action| !keys(action).is_empty()));
+    }
+
+    #[test]
+    fn texto_dos_atalhos() {
+        let shortcut = parse("ctrl+shift+a").unwrap();
+        assert_eq!(format(&shortcut), "Ctrl+Shift+A");
+        assert_eq!(format(&parse("/").unwrap()), "/");
+        assert_eq!(format(&parse("Alt+Up").unwrap()), "Alt+Up");
+        assert!(parse("").is_none());
+        assert_eq!(parse("Ctrl++").unwrap().logical_key, Key::Plus);
+        assert!(parse("Ctrl+Tecla").is_none());
+    }
+}
@@ -0,0 +1,130 @@
+// Playlists temporárias entregues ao player
+//
+// Cada reprodução grava um arquivo com nome único (permissão só do usuário) numa pasta
+// própria dentro do diretório temporário. O arquivo é mantido enquanto o player que o
+// recebeu estiver aberto, e no mínimo por KEEP_FOR; sobras de execuções anteriores são
+// apagadas na inicialização.
+use std::fs;
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::process::Child;
+use std::time::{Duration, Instant, SystemTime};
+
+const DIR_NAME: &str = "m3u8-gui";
+const PREFIX: &str = "play-";
+const SUFFIX: &str = ".m3u";
+
+// Tempo mínimo antes de apagar (o "abrir com o padrão do sistema" termina antes do player ler)
+const KEEP_FOR: Duration = Duration::from_secs(2 * 60);
+
+// Na inicialização, apagar arquivos de execuções anteriores mais velhos que isto
+const STALE_AFTER: Duration = Duration::from_secs(60 * 60);
+
+struct TempEntry {
+    path: PathBuf,
+    child: Option<Child>, // Processo que recebeu o arquivo
+    created: Instant,
+}
+
+#[derive(Default)]
+pub struct TempPlaylists {
+    entries: Vec<TempEntry>,
+}
+
+// Pasta das playlists temporárias ($XDG_RUNTIME_DIR quando existir)
+pub fn dir() -> PathBuf {
+    let base = std::env::var_os("XDG_RUNTIME_DIR")
+        .map(PathBuf::from)
+        .filter(|dir| dir.is_dir())
+        .unwrap_or_else(std::env::temp_dir);
+    base.join(DIR_NAME)
+}
+
+fn ensure_dir() -> io::Result<PathBuf> {
+    let dir = dir();
+    fs::create_dir_all(&dir)?;
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        fs::set_permissions(&dir, fs::Permissions::from_mode(0o700))?;
+    }
+    Ok(dir)
+}
+
+// Apaga playlists deixadas por execuções anteriores
+pub fn cleanup_stale() {
+    let Ok(read_dir) = fs::read_dir(dir()) else {
+        return;
+    };
+    let now = SystemTime::now();
+    for entry in read_dir.flatten() {
+        let name = entry.file_name();
+        let name = name.to_string_lossy();
+        if !name.starts_with(PREFIX) || !name.ends_with(SUFFIX) {
+            continue;
+        }
+        let age = entry
+            .metadata()
+            .and_then(|m| m.modified())
+            .ok()
+            .and_then(|modified| now.duration_since(modified).ok());
+        if age.is_some_and(|age| age >= STALE_AFTER) {
+            let _ = fs::remove_file(entry.path());
+        }
+    }
+}
+
+impl TempPlaylists {
+    // Grava o conteúdo num arquivo novo e retorna o caminho absoluto
+    pub fn write(&mut self, content: &str) -> io::Result<PathBuf> {
+        let dir = ensure_dir()?;
+        let mut file = tempfile::Builder::new()
+            .prefix(PREFIX)
+            .suffix(SUFFIX)
+            .tempfile_in(dir)?;
+        file.write_all(content.as_bytes())?;
+        let (_, path) = file.keep().map_err(|e| e.error)?;
+
+        self.entries.push(TempEntry {
+            path: path.clone(),
+            child: None,
+            created: Instant::now(),
+        });
+        Ok(path)
+    }
+
+    // Associa o processo do player ao arquivo, para só apagar depois que ele fechar. O processo é
+    // esperado em collect (try_wait), então não fica zumbi.
+    pub fn attach_child(&mut self, path: &Path, child: Child) {
+        match self.entries.iter_mut().find(|e| e.path == path) {
+            Some(entry) => entry.child = Some(child),
+            None => crate::player::reap(child),
+        }
+    }
+
+    // Apaga os arquivos que não são mais necessários
+    pub fn collect(&mut self) {
+        self.entries.retain_mut(|entry| {
+            let child_running = entry
+                .child
+                .as_mut()
+                .is_some_and(|child| matches!(child.try_wait(), Ok(None)));
+            if child_running || entry.created.elapsed() < KEEP_FOR {
+                return true;
+            }
+            let _ = fs::remove_file(&entry.path);
+            false
+        });
+    }
+}
+
+impl Drop for TempPlaylists {
+    // Ao fechar o app, apagar o que já foi lido; o resto fica para cleanup_stale
+    fn drop(&mut self) {
+        for entry in &self.entries {
+            if entry.created.elapsed() >= KEEP_FOR {
+                let _ = fs::remove_file(&entry.path);
+            }
+        }
+    }
+}
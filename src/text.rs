@@ -0,0 +1,103 @@
+// Comparação de textos para ordenar títulos, grupos e valores de tags em português
+use std::cmp::Ordering;
+use unicode_normalization::char::is_combining_mark;
+use unicode_normalization::UnicodeNormalization;
+
+// Minúsculas e sem acentos ("Canção" -> "cancao"), para comparar como um leitor compararia
+pub fn fold(text: &str) -> String {
+    text.nfd()
+        .filter(|c| !is_combining_mark(*c))
+        .flat_map(char::to_lowercase)
+        .collect()
+}
+
+// Ordem natural: números comparados pelo valor ("Ep 2" antes de "Ep 10")
+pub fn natural_cmp(a: &str, b: &str) -> Ordering {
+    let mut a = a.chars().peekable();
+    let mut b = b.chars().peekable();
+    loop {
+        match (a.peek().copied(), b.peek().copied()) {
+            (None, None) => return Ordering::Equal,
+            (None, Some(_)) => return Ordering::Less,
+            (Some(_), None) => return Ordering::Greater,
+            (Some(x), Some(y)) if x.is_ascii_digit() && y.is_ascii_digit() => {
+                let x = take_number(&mut a);
+                let y = take_number(&mut b);
+                let (x, y) = (x.trim_start_matches('0'), y.trim_start_matches('0'));
+                let order = x.len().cmp(&y.len()).then_with(|| x.cmp(y));
+                if order != Ordering::Equal {
+                    return order;
+                }
+            }
+            (Some(x), Some(y)) => {
+                if x != y {
+                    return x.cmp(&y);
+                }
+                a.next();
+                b.next();
+            }
+        }
+    }
+}
+
+fn take_number(chars: &mut std::iter::Peekable<std::str::Chars>) -> String {
+    let mut number = String::new();
+    while let Some(c) = chars.next_if(char::is_ascii_digit) {
+        number.push(c);
+    }
+    number
+}
+
+// Texto já sem acentos e em minúsculas, lembrando de qual letra do original veio cada uma
+// (para destacar no título as letras encontradas pela pesquisa)
+#[derive(Clone, Default, Debug, PartialEq)]
+pub struct Folded {
+    pub chars: Vec<char>,
+    pub origin: Vec<usize>, // Índice (em chars) da letra original
+}
+
+impl Folded {
+    pub fn new(text: &str) -> Self {
+        let mut folded = Folded::default();
+        for (index, c) in text.chars().enumerate() {
+            for base in c.nfd().filter(|c| !is_combining_mark(*c)) {
+                for lower in base.to_lowercase() {
+                    folded.chars.push(lower);
+                    folded.origin.push(index);
+                }
+            }
+        }
+        folded
+    }
+
+    // Contém o trecho (já dobrado); devolve a posição em chars
+    pub fn find(&self, needle: &[char]) -> Option<usize> {
+        if needle.is_empty() {
+            return Some(0);
+        }
+        self.chars.windows(needle.len()).position(|window| window == needle)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn ordem_natural() {
+        assert_eq!(natural_cmp("Ep 2", "Ep 10"), Ordering::Less);
+        assert_eq!(natural_cmp("Ep 10", "Ep 9"), Ordering::Greater);
+        assert_eq!(natural_cmp("Ep 02", "Ep 2"), Ordering::Equal);
+        assert_eq!(natural_cmp("Ep 2", "Ep 2b"), Ordering::Less);
+        assert_eq!(natural_cmp("99999999999999999999999", "100000000000000000000000"), Ordering::Less); // Sem estourar
+    }
+
+    #[test]
+    fn sem_acentos_e_minusculo() {
+        assert_eq!(fold("Canção ÁGUA Über"), "cancao agua uber");
+        let folded = Folded::new("Ação");
+        assert_eq!(folded.chars, ['a', 'c', 'a', 'o']);
+        assert_eq!(folded.origin, [0, 1, 2, 3]);
+        assert_eq!(folded.find(&['c', 'a']), Some(1));
+    }
+}
@@ -0,0 +1,351 @@
+// Filtro e ordem da janela "Visualização": playlist do usuário, condições sobre as tags do
+// m3u.json, vídeos não reproduzidos há um tempo e a chave de ordenação. São aplicados junto com a pesquisa em arrange_results.
+use crate::metadata::Metadata;
+use crate::play_history::PlayHistory;
+use crate::text;
+use crate::util;
+use crate::VideoEntry;
+use serde::{Deserialize, Serialize};
+use std::cmp::Ordering;
+use std::collections::{HashMap, HashSet};
+
+#[derive(Serialize, Deserialize, Clone, Default, PartialEq, Debug)]
+#[serde(default)]
+pub struct TagCondition {
+    pub tag: String,
+    pub value: String, // Vazio: basta ter a tag, com qualquer valor
+    pub negate: bool,  // NÃO: o vídeo não pode atender a condição
+}
+
+impl TagCondition {
+    fn matches(&self, metadata: &Metadata, id: &str) -> bool {
+        let value = self.value.trim();
+        let has = match metadata.tag.get(self.tag.trim()).and_then(|ids| ids.get(id)) {
+            None => false,
+            Some(_) if value.is_empty() => true,
+            Some(current) => current.as_deref().is_some_and(|v| v.trim().to_lowercase() == value.to_lowercase()),
+        };
+        has != self.negate
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Default, PartialEq, Debug)]
+#[serde(default)]
+pub struct ViewFilter {
+    pub playlist: Option<String>,     // Mostrar só os vídeos desta playlist do usuário
+    pub conditions: Vec<TagCondition>,
+    pub match_any: bool,              // false: todas as condições (E); true: qualquer uma (OU)
+    pub unplayed_days: u32,           // Só vídeos não reproduzidos nos últimos N dias (0: desligado)
+}
+
+impl ViewFilter {
+    pub fn is_active(&self) -> bool {
+        self.playlist.is_some() || self.unplayed_days > 0 || self.conditions.iter().any(|c| !c.tag.trim().is_empty())
+    }
+
+    // Função que diz se o vídeo com o ID passa no filtro
+    pub fn matcher<'a>(&'a self, metadata: &'a Metadata, history: &'a PlayHistory) -> impl Fn(&str) -> bool + 'a {
+        let conditions: Vec<&TagCondition> = self.conditions.iter().filter(|c| !c.tag.trim().is_empty()).collect();
+        let now = util::now_secs();
+
+        move |id| {
+            if self.playlist.as_ref().is_some_and(|name| !metadata.in_playlist(name, id)) {
+                return false;
+            }
+            if self.unplayed_days > 0
+                && history.stats().get(id).is_some_and(|s| s.days_ago(now) < self.unplayed_days as f64)
+            {
+                return false;
+            }
+            if conditions.is_empty() {
+                return true;
+            }
+            let mut results = conditions.iter().map(|c| c.matches(metadata, id));
+            if self.match_any {
+                results.any(|ok| ok)
+            } else {
+                results.all(|ok| ok)
+            }
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, Default, Debug)]
+pub enum SortKey {
+    #[default]
+    Insertion, // Ordem do arquivo
+    Title,
+    Duration,
+    Group,
+    Host,
+    Rating,
+    PlayCount,   // Vezes reproduzido (play_history.json)
+    LastPlayed,  // Última reprodução; nunca reproduzidos ficam no fim
+    Tag(String), // Valor de uma tag qualquer do m3u.json
+}
+
+impl SortKey {
+    pub const FIXED: [SortKey; 8] = [
+        SortKey::Insertion,
+        SortKey::Title,
+        SortKey::Duration,
+        SortKey::Group,
+        SortKey::Host,
+        SortKey::Rating,
+        SortKey::PlayCount,
+        SortKey::LastPlayed,
+    ];
+
+    pub fn label(&self) -> String {
+        match self {
+            SortKey::Insertion => "Ordem da playlist".to_string(),
+            SortKey::Title => "Título".to_string(),
+            SortKey::Duration => "Duração".to_string(),
+            SortKey::Group => "Grupo (group-title)".to_string(),
+            SortKey::Host => "Site (host da URL)".to_string(),
+            SortKey::Rating => "Nota".to_string(),
+            SortKey::PlayCount => "Vezes reproduzido".to_string(),
+            SortKey::LastPlayed => "Última reprodução".to_string(),
+            SortKey::Tag(name) => format!("Tag: {}", name),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, Default, Debug)]
+#[serde(default)]
+pub struct SortOrder {
+    pub key: SortKey,
+    pub descending: bool,
+}
+
+// Valor de um vídeo para a chave escolhida; textos guardam a forma sem acentos para comparar
+enum SortValue {
+    Number(f64),
+    Text(String, String), // (sem acento e minúsculo, original)
+}
+
+impl SortValue {
+    fn text(value: &str) -> Self {
+        SortValue::Text(text::fold(value), value.to_string())
+    }
+
+    fn cmp(&self, other: &SortValue) -> Ordering {
+        match (self, other) {
+            (SortValue::Number(a), SortValue::Number(b)) => a.total_cmp(b),
+            (SortValue::Text(folded_a, a), SortValue::Text(folded_b, b)) => {
+                text::natural_cmp(folded_a, folded_b).then_with(|| text::natural_cmp(a, b))
+            }
+            (SortValue::Number(_), SortValue::Text(..)) => Ordering::Less,
+            (SortValue::Text(..), SortValue::Number(_)) => Ordering::Greater,
+        }
+    }
+}
+
+fn sort_value(video: &VideoEntry, metadata: &Metadata, history: &PlayHistory, key: &SortKey) -> Option<SortValue> {
+    match key {
+        SortKey::Insertion => None,
+        SortKey::Title => Some(SortValue::text(&video.title)),
+        SortKey::Duration => video.duration.map(SortValue::Number),
+        SortKey::Group => video.group.as_deref().map(SortValue::text),
+        SortKey::Host => host(&video.url).map(|host| SortValue::text(&host)),
+        SortKey::Rating => metadata.rating(&video.id).map(SortValue::Number),
+        SortKey::PlayCount => Some(SortValue::Number(history.play_count(&video.id) as f64)),
+        SortKey::LastPlayed => history.last_played(&video.id).map(|time| SortValue::Number(time as f64)),
+        SortKey::Tag(name) => metadata
+            .tag
+            .get(name)
+            .and_then(|ids| ids.get(&video.id))
+            .map(|value| SortValue::text(value.as_deref().unwrap_or(""))),
+    }
+}
+
+// Ordena índices de `videos` pela chave escolhida. Vídeos sem valor (sem duração, sem a tag...)
+// ficam no fim nos dois sentidos; empates mantêm a ordem do arquivo.
+pub fn sort(
+    indices: &mut Vec<usize>,
+    videos: &[VideoEntry],
+    metadata: &Metadata,
+    history: &PlayHistory,
+    order: &SortOrder,
+) {
+    if order.key == SortKey::Insertion {
+        indices.sort_unstable();
+        if order.descending {
+            indices.reverse();
+        }
+        return;
+    }
+
+    let mut keyed: Vec<(Option<SortValue>, usize)> = indices
+        .iter()
+        .map(|&i| (sort_value(&videos[i], metadata, history, &order.key), i))
+        .collect();
+    keyed.sort_by(|(a, i), (b, j)| {
+        let primary = match (a, b) {
+            (Some(a), Some(b)) if order.descending => b.cmp(a),
+            (Some(a), Some(b)) => a.cmp(b),
+            (Some(_), None) => Ordering::Less,
+            (None, Some(_)) => Ordering::Greater,
+            (None, None) => Ordering::Equal,
+        };
+        primary.then(i.cmp(j))
+    });
+    *indices = keyed.into_iter().map(|(_, i)| i).collect();
+}
+
+// Pesquisa salva (playlist inteligente): a consulta, o filtro de playlist/tags e a ordem,
+// aplicados de novo aos vídeos carregados cada vez que é escolhida
+#[derive(Serialize, Deserialize, Clone, Default, PartialEq, Debug)]
+#[serde(default)]
+pub struct SavedSearch {
+    pub query: String,
+    pub filter: ViewFilter,
+    pub sort: SortOrder,
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, Default, Debug)]
+pub enum GroupKey {
+    #[default]
+    None,
+    Group,       // group-title do #EXTINF (canal/categoria no IPTV)
+    Host,        // Site da URL
+    Letter,      // Primeira letra do título
+    Tag(String), // Valor de uma tag do m3u.json (ex.: artista)
+}
+
+impl GroupKey {
+    pub const FIXED: [GroupKey; 4] = [GroupKey::None, GroupKey::Group, GroupKey::Host, GroupKey::Letter];
+
+    pub fn label(&self) -> String {
+        match self {
+            GroupKey::None => "Sem grupos".to_string(),
+            GroupKey::Group => "Grupo (group-title)".to_string(),
+            GroupKey::Host => "Site (host da URL)".to_string(),
+            GroupKey::Letter => "Primeira letra".to_string(),
+            GroupKey::Tag(name) => format!("Tag: {}", name),
+        }
+    }
+
+    // Nome da seção do vídeo; None vai para a seção "sem ..." no fim
+    fn value(&self, video: &VideoEntry, metadata: &Metadata) -> Option<String> {
+        match self {
+            GroupKey::None => None,
+            GroupKey::Group => video.group.clone(),
+            GroupKey::Host => host(&video.url),
+            GroupKey::Letter => {
+                let first = text::fold(&video.title).chars().find(|c| c.is_alphanumeric())?;
+                Some(if first.is_ascii_digit() { "#".to_string() } else { first.to_uppercase().collect() })
+            }
+            GroupKey::Tag(name) => metadata.tag_value(name, &video.id).map(str::to_string),
+        }
+    }
+
+    fn missing_label(&self) -> &'static str {
+        match self {
+            GroupKey::Group => "(sem grupo)",
+            GroupKey::Host => "(arquivos locais)",
+            GroupKey::Tag(_) => "(sem valor)",
+            _ => "(outros)",
+        }
+    }
+}
+
+// Uma seção da visualização agrupada
+pub struct GroupSection {
+    pub value: Option<String>, // None: vídeos sem valor, seção "sem ..."
+    pub label: String,
+    pub videos: Vec<usize>, // Todos os vídeos da seção, mesmo recolhida
+    pub start: usize,       // Posição do primeiro deles em filtered_videos
+    pub collapsed: bool,    // Recolhida: os vídeos não entram em filtered_videos
+}
+
+// Separa os vídeos filtrados em seções (em ordem alfabética, mantendo a ordem escolhida dentro de
+// cada uma) e deixa em `indices` só os vídeos das seções abertas, seção após seção.
+pub fn group(
+    indices: &mut Vec<usize>,
+    videos: &[VideoEntry],
+    metadata: &Metadata,
+    key: &GroupKey,
+    collapsed: &HashSet<Option<String>>,
+) -> Vec<GroupSection> {
+    if *key == GroupKey::None {
+        return Vec::new();
+    }
+    let mut sections: Vec<(Option<String>, Vec<usize>)> = Vec::new();
+    let mut positions: HashMap<Option<String>, usize> = HashMap::new();
+    for &i in indices.iter() {
+        let value = key.value(&videos[i], metadata);
+        match positions.get(&value) {
+            Some(&position) => sections[position].1.push(i),
+            None => {
+                positions.insert(value.clone(), sections.len());
+                sections.push((value, vec![i]));
+            }
+        }
+    }
+    sections.sort_by(|(a, _), (b, _)| match (a, b) {
+        (Some(a), Some(b)) => SortValue::text(a).cmp(&SortValue::text(b)),
+        (Some(_), None) => Ordering::Less,
+        (None, Some(_)) => Ordering::Greater,
+        (None, None) => Ordering::Equal,
+    });
+
+    indices.clear();
+    sections
+        .into_iter()
+        .map(|(value, members)| {
+            let label = value.clone().unwrap_or_else(|| key.missing_label().to_string());
+            let is_collapsed = collapsed.contains(&value);
+            let start = indices.len();
+            if !is_collapsed {
+                indices.extend(&members);
+            }
+            GroupSection { value, label, videos: members, start, collapsed: is_collapsed }
+        })
+        .collect()
+}
+
+fn host(url: &str) -> Option<String> {
+    let url = reqwest::Url::parse(url).ok()?;
+    Some(url.host_str()?.trim_start_matches("www.").to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::m3u;
+
+    fn video(title: &str, group: Option<&str>) -> VideoEntry {
+        let url = format!("https://a/{}", title);
+        VideoEntry::from(m3u::M3uEntry {
+            title: title.to_string(),
+            id: m3u::video_id(&url),
+            url,
+            logo: None,
+            group: group.map(str::to_string),
+            duration: None,
+            http: m3u::HttpOptions::default(),
+            raw_lines: Vec::new(),
+        })
+    }
+
+    #[test]
+    fn grupo_chamado_sem_grupo_nao_se_mistura() {
+        let videos = vec![video("a", Some("(sem grupo)")), video("b", None), video("c", Some("Filmes"))];
+        let metadata = Metadata::default();
+
+        let mut indices = vec![0, 1, 2];
+        let sections = group(&mut indices, &videos, &metadata, &GroupKey::Group, &HashSet::new());
+        let labels: Vec<&str> = sections.iter().map(|s| s.label.as_str()).collect();
+        assert_eq!(labels, ["(sem grupo)", "Filmes", "(sem grupo)"]);
+        assert_eq!(indices, [0, 2, 1]);
+
+        // Recolher a seção dos vídeos sem grupo não recolhe o grupo de mesmo nome
+        let collapsed = HashSet::from([None]);
+        let mut indices = vec![0, 1, 2];
+        let sections = group(&mut indices, &videos, &metadata, &GroupKey::Group, &collapsed);
+        assert!(!sections[0].collapsed);
+        assert!(sections[2].collapsed);
+        assert_eq!(indices, [0, 2]);
+    }
+}
@@ -0,0 +1,63 @@
+// Leitura de playlists XSPF (XML), coladas de outros programas como o VLC
+use crate::m3u::{self, HttpOptions, M3uEntry};
+use regex::Regex;
+
+pub fn looks_like_xspf(text: &str) -> bool {
+    let text = text.trim_start();
+    text.starts_with('<') && text.contains("<track")
+}
+
+pub fn parse(text: &str) -> Vec<M3uEntry> {
+    let track_regex = Regex::new(r"(?s)<track\b[^>]*>(.*?)</track>").unwrap();
+    let field_regex = |name: &str| Regex::new(&format!(r"(?s)<{0}\b[^>]*>(.*?)</{0}>", name)).unwrap();
+    let location_regex = field_regex("location");
+    let title_regex = field_regex("title");
+    let creator_regex = field_regex("creator");
+    let image_regex = field_regex("image");
+    let duration_regex = field_regex("duration");
+
+    let field = |regex: &Regex, track: &str| {
+        regex
+            .captures(track)
+            .map(|c| unescape(c[1].trim()))
+            .filter(|value| !value.is_empty())
+    };
+
+    let mut entries = Vec::new();
+    for captures in track_regex.captures_iter(text) {
+        let track = &captures[1];
+        let Some(url) = field(&location_regex, track) else {
+            continue;
+        };
+        // "Artista - Título" quando houver <creator>, como os players mostram
+        let title = match (field(&creator_regex, track), field(&title_regex, track)) {
+            (Some(creator), Some(title)) => format!("{} - {}", creator, title),
+            (None, Some(title)) => title,
+            _ => m3u::fallback_title(&url),
+        };
+        entries.push(M3uEntry {
+            title,
+            id: m3u::video_id(&url),
+            logo: field(&image_regex, track),
+            group: None,
+            // <duration> vem em milissegundos
+            duration: field(&duration_regex, track)
+                .and_then(|ms| ms.parse::<f64>().ok())
+                .map(|ms| ms / 1000.0)
+                .filter(|seconds| *seconds > 0.0),
+            http: HttpOptions::default(),
+            raw_lines: Vec::new(),
+            url,
+        });
+    }
+    entries
+}
+
+// Entidades XML básicas (&amp; por último para não desfazer duas vezes)
+fn unescape(text: &str) -> String {
+    text.replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&apos;", "'")
+        .replace("&amp;", "&")
+}